@@ -8,9 +8,9 @@ use std::{
 use clap::{builder::PossibleValue, ArgGroup, CommandFactory, Parser};
 use indicate::{
     advisory::AdvisoryClient, execute_query_with_adapter, query::FullQuery,
-    query::FullQueryBuilder, repo::github::GitHubClient,
-    util::transparent_results, CargoOpt, IndicateAdapter,
-    IndicateAdapterBuilder, ManifestPath,
+    query::FullQueryBuilder, registry_index::RegistryIndexClient,
+    repo::github::GitHubClient, util::transparent_results, CargoOpt,
+    IndicateAdapter, IndicateAdapterBuilder, ManifestPath,
 };
 mod util;
 
@@ -100,7 +100,7 @@ struct IndicateCli {
     /// Path to a Cargo.toml file, or a directory containing one
     #[arg(
         last(true),
-        required_unless_present = "show_schema",
+        required_unless_present_any = ["show_schema", "metadata_file"],
         default_value = "./",
         value_hint = clap::ValueHint::AnyPath
     )]
@@ -173,6 +173,30 @@ struct IndicateCli {
     #[arg(short, long, num_args=0.., conflicts_with = "all_features")]
     features: Option<Vec<String>>,
 
+    /// Only include dependencies used when compiling for this target triple,
+    /// like `x86_64-unknown-linux-gnu`
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+
+    /// Use metadata saved from `cargo metadata --format-version 1` instead of
+    /// generating it, or `-` to read it from stdin
+    ///
+    /// The package path is not used to resolve dependencies in this case,
+    /// since the metadata describes its own root package.
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = clap::ValueHint::FilePath,
+        conflicts_with_all = [
+            "package_name",
+            "all_features",
+            "no_default_features",
+            "features",
+            "target"
+        ]
+    )]
+    metadata_file: Option<PathBuf>,
+
     /// Use a local `advisory-db` database instead of fetching the default
     /// from GitHub
     #[arg(long, value_hint = clap::ValueHint::DirPath)]
@@ -183,6 +207,23 @@ struct IndicateCli {
     #[arg(long, conflicts_with = "advisory_db_dir")]
     cached_advisory_db: bool,
 
+    /// Use a crates.io database dump (`db-dump.tar.gz`) instead of the
+    /// crates.io API
+    ///
+    /// This avoids the rate limit of one request per second when querying
+    /// crates.io data for many packages. The latest dump is available at
+    /// https://static.crates.io/db-dump.tar.gz.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    crates_io_db_dump: Option<PathBuf>,
+
+    /// Use a local registry index for the versions of crates and if they are
+    /// yanked, instead of crates.io
+    ///
+    /// This can be a directory in `~/.cargo/registry/index`, a sparse index
+    /// mirror or a git index checkout.
+    #[arg(long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
+    registry_index: Option<PathBuf>,
+
     /// If the program should sleep while awaiting a new GitHub API quota, if it
     /// is reached during execution
     ///
@@ -372,16 +413,27 @@ fn main() {
         }
     }
 
-    let manifest_path = if let Some(package_name) = cli.package_name {
-        ManifestPath::with_package_name(&cli.package, &package_name)
-    } else {
-        ManifestPath::new(&cli.package)
-    };
-
     // How we execute the query depends on if the user defined any special
     // requirements for the adapter
 
-    let mut b = IndicateAdapterBuilder::new(manifest_path);
+    let mut b = if let Some(p) = &cli.metadata_file {
+        let metadata = util::read_metadata(p).unwrap_or_else(|e| {
+            let msg = format!(
+                "could not read metadata from {} due to error: {e}",
+                p.to_string_lossy()
+            );
+            cmd.error(clap::error::ErrorKind::ValueValidation, msg)
+                .exit();
+        });
+        IndicateAdapterBuilder::from_metadata(metadata)
+    } else {
+        let manifest_path = if let Some(package_name) = cli.package_name {
+            ManifestPath::with_package_name(&cli.package, &package_name)
+        } else {
+            ManifestPath::new(&cli.package)
+        };
+        IndicateAdapterBuilder::new(manifest_path)
+    };
 
     // Clap will ensure that these do not mismatch
     if cli.all_features {
@@ -396,6 +448,10 @@ fn main() {
         }
     }
 
+    if let Some(t) = cli.target {
+        b = b.target_platform(t);
+    }
+
     // These two are mutually exclusive, but that is checked by clap already
     if let Some(p) = cli.advisory_db_dir {
         let ac = AdvisoryClient::from_path(p.as_path()).unwrap_or_else(|e| {
@@ -414,6 +470,14 @@ fn main() {
         b = b.advisory_client(ac);
     }
 
+    if let Some(p) = cli.crates_io_db_dump {
+        b = b.crates_io_db_dump(p);
+    }
+
+    if let Some(p) = cli.registry_index {
+        b = b.registry_index(RegistryIndexClient::new(&p));
+    }
+
     if cli.await_github_quota {
         b = b.github_client(GitHubClient::new(true));
     }
@@ -1,10 +1,13 @@
 use std::{
     collections::BTreeSet,
+    error::Error,
     ffi::{OsStr, OsString},
-    fs,
+    fs, io,
     path::{Path, PathBuf},
 };
 
+use indicate::Metadata;
+
 /// Ensures the parent directories exists, and if they don't, attempt to create
 /// them
 pub(crate) fn ensure_parents_exist(path: &Path) -> Result<(), std::io::Error> {
@@ -14,6 +17,17 @@ pub(crate) fn ensure_parents_exist(path: &Path) -> Result<(), std::io::Error> {
     Ok(())
 }
 
+/// Reads metadata saved from `cargo metadata --format-version 1`, from stdin
+/// if the path is `-`
+pub(crate) fn read_metadata(path: &Path) -> Result<Metadata, Box<dyn Error>> {
+    let raw_metadata = if path == Path::new("-") {
+        io::read_to_string(io::stdin())?
+    } else {
+        fs::read_to_string(path)?
+    };
+    Ok(serde_json::from_str(&raw_metadata)?)
+}
+
 /// Creates paths for output files, named according to the input queries
 ///
 /// To avoid overwriting when we have duplicate query name prefixes, a number is
@@ -102,7 +116,7 @@ pub(crate) fn file_prefix(path: &Path) -> Option<&OsStr> {
         };
 
         // Split the file name to at most 2 parts, separated by '.'
-        let mut iter = trimmed.splitn(2, |c| c == '.');
+        let mut iter = trimmed.splitn(2, '.');
         let before = iter.next();
         let after = iter.next();
 
@@ -193,7 +207,7 @@ mod test {
 
         let expected = expected_strs
             .iter()
-            .map(|s| PathBuf::from_str(*s).unwrap())
+            .map(|s| PathBuf::from_str(s).unwrap())
             .collect::<Vec<_>>();
         assert_eq!(res, expected);
     }
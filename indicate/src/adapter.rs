@@ -1,8 +1,13 @@
-use cargo_metadata::{CargoOpt, Metadata, Package, PackageId};
+use cargo_metadata::{CargoOpt, DependencyKind, Metadata, Package, PackageId};
+use cfg_expr::targets::get_builtin_target_by_triple;
 use chrono::{NaiveDate, NaiveDateTime};
 use once_cell::unsync::OnceCell;
 use std::{
-    cell::RefCell, collections::HashMap, rc::Rc, str::FromStr, sync::Arc,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+    str::FromStr,
+    sync::Arc,
 };
 use trustfall::{
     provider::{
@@ -15,8 +20,14 @@ use trustfall::{
 
 use crate::{
     advisory::AdvisoryClient,
+    dependency::{DependencyEdge, DependencyPath},
+    duplicate::DuplicateGroup,
+    feature::{Feature, FeatureValue},
     geiger::GeigerClient,
+    license::{LicenseExpression, LicenseFile},
+    registry::{registry_key, RegistryClients, RegistryPackage},
     repo::{github::GitHubClient, RepoId},
+    source::{Source, SourceKind},
     vertex::Vertex,
     ManifestPath,
 };
@@ -25,8 +36,9 @@ use crate::{
     util,
 };
 use crate::{
-    crates_io::CratesIoClient, geiger::GeigerOutput, IndicateAdapterBuilder,
-    NameVersion,
+    crates_io::{user_kind, DOWNLOAD_HISTORY_DAYS},
+    geiger::GeigerOutput,
+    IndicateAdapterBuilder, NameVersion,
 };
 
 pub mod adapter_builder;
@@ -35,6 +47,24 @@ pub mod adapter_builder;
 pub(crate) type DirectDependencyMap = HashMap<PackageId, Rc<Vec<PackageId>>>;
 pub(crate) type PackageMap = HashMap<PackageId, Rc<Package>>;
 
+/// All packages sharing a crate name, sorted by version
+pub(crate) type PackageNameMap = BTreeMap<String, Rc<Vec<Rc<Package>>>>;
+
+/// Packages that directly depend on a package, i.e. the inverse of a
+/// [`DirectDependencyMap`]
+pub(crate) type DependentMap = HashMap<PackageId, Rc<Vec<PackageId>>>;
+
+/// The shortest distance from a root package to a package, following normal
+/// dependencies
+pub(crate) type DepthMap = HashMap<PackageId, usize>;
+
+/// All dependency edges going out from a package, of all dependency kinds
+pub(crate) type DependencyEdgeMap =
+    HashMap<PackageId, Rc<Vec<Rc<DependencyEdge>>>>;
+
+/// Features enabled for a package in the resolved dependency graph
+pub(crate) type EnabledFeatureMap = HashMap<PackageId, Rc<Vec<String>>>;
+
 macro_rules! resolve_code_stats {
     ($getter:ident) => {
         |v| {
@@ -67,39 +97,190 @@ pub struct IndicateAdapter {
     features: Vec<CargoOpt>,
     metadata: Rc<Metadata>,
     packages: OnceCell<Rc<PackageMap>>,
-    direct_dependencies: OnceCell<Rc<DirectDependencyMap>>,
+    packages_by_name: OnceCell<Rc<PackageNameMap>>,
+    direct_dependencies:
+        RefCell<HashMap<DependencyKind, Rc<DirectDependencyMap>>>,
+    dependents: RefCell<HashMap<DependencyKind, Rc<DependentMap>>>,
+    depths: OnceCell<Rc<DepthMap>>,
+    dependency_edges: OnceCell<Rc<DependencyEdgeMap>>,
+    enabled_features: OnceCell<Rc<EnabledFeatureMap>>,
     gh_client: Rc<RefCell<GitHubClient>>,
     advisory_client: OnceCell<Rc<AdvisoryClient>>,
     geiger_client: OnceCell<Rc<GeigerClient>>,
-    crates_io_client: OnceCell<Rc<RefCell<CratesIoClient>>>,
+    registries: Rc<RegistryClients>,
 }
 
 /// The functions here are essentially the fields on the `RootQuery`
 impl IndicateAdapter {
+    /// Retrieves the root package, or nothing if the manifest is a virtual
+    /// workspace manifest
     fn root_package(&self) -> VertexIterator<'static, Vertex> {
-        let root = self.metadata.root_package().expect("no root package found");
-        let v = Vertex::Package(Rc::new(root.clone()));
-        Box::new(std::iter::once(v))
+        match self.metadata.root_package() {
+            Some(root) => Box::new(std::iter::once(Vertex::Package(
+                Rc::clone(self.packages().get(&root.id).unwrap()),
+            ))),
+            None => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// Retrieves an iterator over all members of the workspace, which is only
+    /// the root package if the manifest is not a workspace
+    fn workspace_members(&self) -> VertexIterator<'static, Vertex> {
+        let mut member_ids = self.metadata.workspace_members.clone();
+        member_ids.sort();
+        let packages = self.packages();
+        let members = member_ids
+            .iter()
+            .map(|pid| Vertex::Package(Rc::clone(packages.get(pid).unwrap())))
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        Box::new(members)
+    }
+
+    /// Retrieves one [`DuplicateGroup`] per crate name that resolves to more
+    /// than one version, sorted by name
+    fn duplicated_packages(&self) -> VertexIterator<'static, Vertex> {
+        let groups = self
+            .packages_by_name()
+            .iter()
+            // Packages are sorted by version, and the same version may come
+            // from more than one source
+            .filter(|(_, packages)| {
+                packages.windows(2).any(|w| w[0].version != w[1].version)
+            })
+            .map(|(name, packages)| {
+                Vertex::DuplicateGroup(Rc::new(DuplicateGroup::new(
+                    name.clone(),
+                    Rc::clone(packages),
+                )))
+            })
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        Box::new(groups)
+    }
+
+    /// Retrieves all dependencies of the provided kinds with a license that is
+    /// incompatible with the license of the root package depending on them
+    fn license_conflicts(
+        &self,
+        kinds: &[DependencyKind],
+    ) -> VertexIterator<'static, Vertex> {
+        let packages = self.packages();
+        let mut conflict_ids = Vec::new();
+        for root_id in self.root_package_ids() {
+            let root_license =
+                packages.get(&root_id).unwrap().license.as_deref();
+            for id in self.reachable_dependency_ids(&root_id, kinds) {
+                let Some(license) = &packages.get(&id).unwrap().license else {
+                    continue;
+                };
+                let compatible = LicenseExpression::new(license, None)
+                    .with_root_license(root_license)
+                    .is_compatible_with_root();
+                if id != root_id && compatible == Some(false) {
+                    conflict_ids.push(id);
+                }
+            }
+        }
+
+        conflict_ids.sort();
+        conflict_ids.dedup();
+        let conflicts = conflict_ids
+            .iter()
+            .map(|pid| Vertex::Package(Rc::clone(packages.get(pid).unwrap())))
+            .collect::<Vec<_>>()
+            .into_iter();
+
+        Box::new(conflicts)
+    }
+
+    /// Retrieves the IDs of the packages that are the roots of the dependency
+    /// graph, i.e. the root package or all workspace members for a virtual
+    /// workspace
+    fn root_package_ids(&self) -> Vec<PackageId> {
+        match self.metadata.root_package() {
+            Some(root) => vec![root.id.clone()],
+            None => self.metadata.workspace_members.clone(),
+        }
+    }
+
+    /// Retrieves the ID of a workspace member by its package name, or `None`
+    /// if no workspace member with that name exists
+    fn workspace_member_id(&self, name: &str) -> Option<PackageId> {
+        self.metadata
+            .workspace_members
+            .iter()
+            .find(|pid| self.packages().get(*pid).unwrap().name == name)
+            .cloned()
     }
 
-    /// Retrieves an iterator over all package IDs of normal dependencies
-    /// (transitive and direct)
-    fn dependency_ids(&self, include_root: bool) -> Vec<PackageId> {
-        // Use the direct, normal dependencies we already resolved when
-        // parsing the metadata
-        let mut dependency_package_ids = self
-            .direct_dependencies()
-            .values()
-            .flat_map(|r| r.to_vec())
+    /// Retrieves the IDs of all packages reachable from a package by following
+    /// dependencies of the provided kinds, excluding the package itself unless
+    /// it depends on itself
+    ///
+    /// The normal dependencies of every package reached are always followed,
+    /// since they are needed to build it, e.g. to run a build script.
+    fn reachable_dependency_ids(
+        &self,
+        package_id: &PackageId,
+        kinds: &[DependencyKind],
+    ) -> Vec<PackageId> {
+        let direct_dependencies = kinds
+            .iter()
+            .map(|k| self.direct_dependencies(*k))
             .collect::<Vec<_>>();
+        let normal_dependencies =
+            self.direct_dependencies(DependencyKind::Normal);
+
+        let mut reachable: Vec<PackageId> = Vec::new();
+        // Whether the package was reached as a dependency is kept alongside it
+        let mut stack = vec![(package_id, false)];
+        while let Some((pid, is_dependency)) = stack.pop() {
+            let followed = direct_dependencies
+                .iter()
+                .chain(is_dependency.then_some(&normal_dependencies));
+            for dd in followed {
+                for id in dd.get(pid).into_iter().flat_map(|ids| ids.iter()) {
+                    if !reachable.contains(id) {
+                        reachable.push(id.clone());
+                        stack.push((id, true));
+                    }
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Retrieves an iterator over all package IDs of dependencies of the
+    /// provided kinds (transitive and direct)
+    ///
+    /// If `member` is provided, only dependencies of that workspace member are
+    /// included, and it is treated as the root package.
+    fn dependency_ids(
+        &self,
+        include_root: bool,
+        kinds: &[DependencyKind],
+        member: Option<&PackageId>,
+    ) -> Vec<PackageId> {
+        let root_ids = match member {
+            Some(member_id) => vec![member_id.clone()],
+            None => self.root_package_ids(),
+        };
+
+        let mut dependency_package_ids = Vec::new();
+        for root_id in &root_ids {
+            let mut ids = self.reachable_dependency_ids(root_id, kinds);
+            // A root may be a dependency of itself through dev-dependencies,
+            // but members of a virtual workspace may depend on each other
+            ids.retain(|pid| pid != root_id);
+            dependency_package_ids.extend(ids);
+        }
 
-        // Remove root if requrested (is always included in dependency graph)
         if include_root {
-            let root_package = self
-                .metadata
-                .root_package()
-                .expect("could not resolve root node");
-            dependency_package_ids.push(root_package.id.clone());
+            dependency_package_ids.extend(root_ids);
         }
 
         // Sorting gives us same output every time, and allows for
@@ -113,13 +294,24 @@ impl IndicateAdapter {
     /// Retrieves an iterator over all dependencies, optionally including the
     /// root package
     ///
-    /// Only returns dependencies that are of the 'normal' kind, i.e. no
-    /// dev or build dependencies.
+    /// Only returns dependencies that are of the provided kinds, such as only
+    /// 'normal' dependencies, i.e. no dev or build dependencies.
     fn dependencies(
         &self,
         include_root: bool,
+        kinds: &[DependencyKind],
+        member: Option<&str>,
     ) -> VertexIterator<'static, Vertex> {
-        let dependency_package_ids = self.dependency_ids(include_root);
+        let member_id = match member {
+            Some(name) => match self.workspace_member_id(name) {
+                Some(id) => Some(id),
+                // An unknown member has no dependencies
+                None => return Box::new(std::iter::empty()),
+            },
+            None => None,
+        };
+        let dependency_package_ids =
+            self.dependency_ids(include_root, kinds, member_id.as_ref());
         // We must call `.collect()`, to ensure lifetimes by enforcing the
         // `Rc::clone`. It will not affect the resolution or laziness, since
         // this is a starting node
@@ -137,27 +329,29 @@ impl IndicateAdapter {
 
     /// Retrieves a vector of all transitive dependency IDs, i.e. dependencies
     /// that are dependencies of direct dependencies
-    fn transitive_dependency_ids(&self) -> Vec<PackageId> {
+    fn transitive_dependency_ids(
+        &self,
+        kinds: &[DependencyKind],
+    ) -> Vec<PackageId> {
         // Transitive dependencies are those that are direct dependencies to
-        // anything but the root package
-        let root_package_id = self
-            .metadata
-            .root_package()
-            .expect("could not resolve root node")
-            .id
-            .clone();
-        let mut transitive_dependency_ids = self
-            .direct_dependencies()
+        // anything but the root package(s)
+        let root_package_ids = self.root_package_ids();
+        let mut transitive_dependency_ids = kinds
             .iter()
-            .filter_map(|(p, dir_deps)| {
-                // Filter out the root package
-                if *p == root_package_id {
-                    None
-                } else {
-                    Some((*(*dir_deps)).clone())
-                }
+            .flat_map(|k| {
+                self.direct_dependencies(*k)
+                    .iter()
+                    .filter_map(|(p, dir_deps)| {
+                        // Filter out the root package
+                        if root_package_ids.contains(p) {
+                            None
+                        } else {
+                            Some((*(*dir_deps)).clone())
+                        }
+                    })
+                    .flatten()
+                    .collect::<Vec<_>>()
             })
-            .flatten()
             .collect::<Vec<_>>();
 
         // Sorting gives us same output every time, and allows for
@@ -171,10 +365,13 @@ impl IndicateAdapter {
     /// Retrieves an iterator over all transitive dependencies (dependencies
     /// of direct dependencies to the root package)
     ///
-    /// Only returns dependencies that are of the 'normal' kind, i.e. no
-    /// dev or build dependencies.
-    fn transitive_dependencies(&self) -> VertexIterator<'static, Vertex> {
-        let dependency_package_ids = self.transitive_dependency_ids();
+    /// Only returns dependencies that are of the provided kinds, such as only
+    /// 'normal' dependencies, i.e. no dev or build dependencies.
+    fn transitive_dependencies(
+        &self,
+        kinds: &[DependencyKind],
+    ) -> VertexIterator<'static, Vertex> {
+        let dependency_package_ids = self.transitive_dependency_ids(kinds);
         // We must call `.collect()`, to ensure lifetimes by enforcing the
         // `Rc::clone`. It will not affect the resolution or laziness, since
         // this is a starting node
@@ -214,16 +411,102 @@ impl IndicateAdapter {
         Rc::clone(p)
     }
 
-    /// Retrieves a new counted reference to this adapters [`PackageMap`], or
-    /// retrieves it from metadata if it does not exist
+    /// Retrieves a new counted reference to this adapters [`PackageNameMap`],
+    /// or creates it from the packages if it does not exist
+    #[must_use]
+    fn packages_by_name(&self) -> Rc<PackageNameMap> {
+        let p = self.packages_by_name.get_or_init(|| {
+            Rc::new(util::get_packages_by_name(&self.packages()))
+        });
+        Rc::clone(p)
+    }
+
+    /// Retrieves a new counted reference to this adapters
+    /// [`DirectDependencyMap`] for a dependency kind, or retrieves it from
+    /// metadata if it does not exist
     #[must_use]
-    fn direct_dependencies(&self) -> Rc<DirectDependencyMap> {
-        let dd = self.direct_dependencies.get_or_init(|| {
+    fn direct_dependencies(
+        &self,
+        kind: DependencyKind,
+    ) -> Rc<DirectDependencyMap> {
+        let mut dd = self.direct_dependencies.borrow_mut();
+        let kind_dd = dd.entry(kind).or_insert_with(|| {
             let direct_dependencies =
-                util::get_direct_dependencies(&self.metadata);
+                util::get_direct_dependencies(&self.metadata, kind);
             Rc::new(direct_dependencies)
         });
-        Rc::clone(dd)
+        Rc::clone(kind_dd)
+    }
+
+    /// Retrieves a new counted reference to this adapters [`DependentMap`]
+    /// for a dependency kind, or creates it from the [`DirectDependencyMap`]
+    /// of that kind if it does not exist
+    #[must_use]
+    fn dependents(&self, kind: DependencyKind) -> Rc<DependentMap> {
+        let direct_dependencies = self.direct_dependencies(kind);
+        let mut d = self.dependents.borrow_mut();
+        let kind_d = d.entry(kind).or_insert_with(|| {
+            Rc::new(util::get_dependents(&direct_dependencies))
+        });
+        Rc::clone(kind_d)
+    }
+
+    /// Retrieves a new counted reference to this adapters [`DepthMap`], or
+    /// calculates it from the normal dependencies if it does not exist
+    #[must_use]
+    fn depths(&self) -> Rc<DepthMap> {
+        let d = self.depths.get_or_init(|| {
+            Rc::new(util::get_depths(
+                &self.direct_dependencies(DependencyKind::Normal),
+                &self.root_package_ids(),
+            ))
+        });
+        Rc::clone(d)
+    }
+
+    /// Parses the `kinds` parameter used to select what dependency kinds
+    /// to use when resolving dependencies, defaulting to only normal
+    /// dependencies
+    fn dependency_kinds(parameters: &EdgeParameters) -> Vec<DependencyKind> {
+        parameters
+            .get("kinds")
+            .and_then(|k| k.as_vec_with(FieldValue::as_str))
+            .map_or_else(
+                || vec![DependencyKind::Normal],
+                |k| {
+                    k.into_iter()
+                        .map(|s| {
+                            util::parse_dependency_kind(s).unwrap_or_else(
+                                || panic!("unknown dependency kind parameter: {s}"),
+                            )
+                        })
+                        .collect()
+                },
+            )
+    }
+
+    /// Retrieves a new counted reference to this adapters
+    /// [`DependencyEdgeMap`], or retrieves it from metadata if it does not
+    /// exist
+    #[must_use]
+    fn dependency_edges(&self) -> Rc<DependencyEdgeMap> {
+        let de = self.dependency_edges.get_or_init(|| {
+            let dependency_edges =
+                util::get_dependency_edges(&self.metadata, &self.packages());
+            Rc::new(dependency_edges)
+        });
+        Rc::clone(de)
+    }
+
+    /// Retrieves a new counted reference to this adapters
+    /// [`EnabledFeatureMap`], or retrieves it from metadata if it does not
+    /// exist
+    #[must_use]
+    fn enabled_features(&self) -> Rc<EnabledFeatureMap> {
+        let ef = self.enabled_features.get_or_init(|| {
+            Rc::new(util::get_enabled_features(&self.metadata))
+        });
+        Rc::clone(ef)
     }
 
     /// Retrieves a new counted reference to this adapters [`GitHubClient`]
@@ -269,30 +552,38 @@ impl IndicateAdapter {
         Rc::clone(sgc)
     }
 
-    /// Retrieves or creates a new default [`CratesIoClient`] if none is set
+    /// Retrieves the clients for `crates.io` and alternative registries,
+    /// which are created when first used
     #[must_use]
-    fn crates_io_client(&self) -> Rc<RefCell<CratesIoClient>> {
-        let c = self
-            .crates_io_client
-            .get_or_init(|| Rc::new(RefCell::new(CratesIoClient::default())));
-        Rc::clone(c)
+    fn registries(&self) -> Rc<RegistryClients> {
+        Rc::clone(&self.registries)
     }
 
     fn get_dependencies(
         packages: Rc<PackageMap>,
-        direct_dependencies: &Rc<DirectDependencyMap>,
+        direct_dependencies: &[Rc<DirectDependencyMap>],
         package_id: &PackageId,
     ) -> VertexIterator<'static, Vertex> {
-        let dd = Rc::clone(direct_dependencies);
-        let dependency_ids = dd.get(package_id).unwrap_or_else(|| {
-            panic!(
-                "Could not extract dependency IDs for package {}",
-                &package_id
-            )
-        });
+        // A package may be a dependency of several kinds, but should only
+        // appear once
+        let mut dependency_ids: Vec<&PackageId> = Vec::new();
+        for dd in direct_dependencies {
+            let kind_dependency_ids = dd.get(package_id).unwrap_or_else(|| {
+                panic!(
+                    "Could not extract dependency IDs for package {}",
+                    &package_id
+                )
+            });
+
+            for id in kind_dependency_ids.iter() {
+                if !dependency_ids.contains(&id) {
+                    dependency_ids.push(id);
+                }
+            }
+        }
 
         let dependencies = dependency_ids
-            .iter()
+            .into_iter()
             .map(move |id| {
                 let p = packages.get(id).unwrap();
                 Vertex::Package(Rc::clone(p))
@@ -303,6 +594,39 @@ impl IndicateAdapter {
         Box::new(dependencies)
     }
 
+    /// Creates a [`Feature`] of a package, checking if it is enabled in the
+    /// resolved dependency graph
+    fn get_feature(
+        enabled_features: &EnabledFeatureMap,
+        package: Rc<Package>,
+        name: &str,
+    ) -> Feature {
+        let enabled = enabled_features
+            .get(&package.id)
+            .is_some_and(|ef| ef.iter().any(|f| f == name));
+        Feature::new(package, name.to_string(), enabled)
+    }
+
+    /// Finds the resolved dependency a package refers to by name in its
+    /// feature definitions, i.e. its renamed name if it has one
+    ///
+    /// Returns `None` if the dependency is not part of the resolved dependency
+    /// graph, such as optional dependencies that are not enabled.
+    fn get_feature_dependency(
+        dependency_edges: &DependencyEdgeMap,
+        package: &Package,
+        name: &str,
+    ) -> Option<Rc<DependencyEdge>> {
+        dependency_edges.get(&package.id).and_then(|edges| {
+            edges
+                .iter()
+                .find(|e| {
+                    e.renamed_as().unwrap_or(e.package().name.as_str()) == name
+                })
+                .cloned()
+        })
+    }
+
     /// Returns a form of repository, i.e. a variant that implements the
     /// `schema.trustfall.graphql` `repository` interface
     fn get_repository_from_url(
@@ -341,9 +665,21 @@ impl<'a> BasicAdapter<'a> for IndicateAdapter {
                 // to match the schema
                 let include_root =
                     parameters.get("includeRoot").unwrap().as_bool().unwrap();
-                self.dependencies(include_root)
+                let kinds = Self::dependency_kinds(parameters);
+                let member =
+                    parameters.get("member").and_then(FieldValue::as_str);
+                self.dependencies(include_root, &kinds, member)
+            }
+            "WorkspaceMembers" => self.workspace_members(),
+            "DuplicatedPackages" => self.duplicated_packages(),
+            "LicenseConflicts" => {
+                let kinds = Self::dependency_kinds(parameters);
+                self.license_conflicts(&kinds)
+            }
+            "TransitiveDependencies" => {
+                let kinds = Self::dependency_kinds(parameters);
+                self.transitive_dependencies(&kinds)
             }
-            "TransitiveDependencies" => self.transitive_dependencies(),
             e => {
                 unreachable!("edge {e} has no resolution as a starting vertex")
             }
@@ -399,105 +735,636 @@ impl<'a> BasicAdapter<'a> for IndicateAdapter {
                     )
                 })
             }
-            ("Package", "sourcePath") => resolve_property_with(contexts, |v| {
-                let package = v.as_package().unwrap();
-                FieldValue::String(
-                    util::local_package_path(package).to_string_lossy().into(),
-                )
-            }),
-            ("CratesIoStats", "totalDownloads") => {
-                let crates_io_client = self.crates_io_client();
+            ("Package", "sourcePath") => resolve_property_with(contexts, |v| {
+                let package = v.as_package().unwrap();
+                FieldValue::String(
+                    util::local_package_path(package).to_string_lossy().into(),
+                )
+            }),
+            ("Package", "description") => resolve_property_with(
+                contexts,
+                field_property!(as_package, description),
+            ),
+            ("Package", "authors") => resolve_property_with(
+                contexts,
+                field_property!(as_package, authors),
+            ),
+            ("Package", "homepage") => resolve_property_with(
+                contexts,
+                field_property!(as_package, homepage),
+            ),
+            ("Package", "documentation") => resolve_property_with(
+                contexts,
+                field_property!(as_package, documentation),
+            ),
+            ("Package", "readme") => resolve_property_with(
+                contexts,
+                field_property!(as_package, readme, {
+                    match readme {
+                        Some(r) => r.to_string().into(),
+                        None => FieldValue::Null,
+                    }
+                }),
+            ),
+            ("Package", "edition") => resolve_property_with(
+                contexts,
+                field_property!(as_package, edition, {
+                    edition.as_str().into()
+                }),
+            ),
+            ("Package", "rustVersion") => {
+                resolve_property_with(contexts, |v| {
+                    let package = v.as_package().unwrap();
+                    util::rust_version(package).into()
+                })
+            }
+            ("Package", "links") => resolve_property_with(
+                contexts,
+                field_property!(as_package, links),
+            ),
+            ("Package", "publish") => resolve_property_with(
+                contexts,
+                field_property!(as_package, publish),
+            ),
+            ("Package", "defaultRun") => resolve_property_with(
+                contexts,
+                field_property!(as_package, default_run),
+            ),
+            ("Package", "sourceKind") => resolve_property_with(contexts, |v| {
+                let package = v.as_package().unwrap();
+                Source::from(package.as_ref()).kind().as_str().into()
+            }),
+            ("Package", "isProcMacro") => {
+                resolve_property_with(contexts, |v| {
+                    let package = v.as_package().unwrap();
+                    util::is_proc_macro(package).into()
+                })
+            }
+            ("Package", "hasBuildScript") => {
+                resolve_property_with(contexts, |v| {
+                    let package = v.as_package().unwrap();
+                    util::has_build_script(package).into()
+                })
+            }
+            ("Package", "transitiveDependentsCount") => {
+                let dependents = self.dependents(DependencyKind::Normal);
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_package().unwrap();
+                    let count = util::transitive_dependents_count(
+                        &dependents,
+                        &package.id,
+                    );
+                    FieldValue::Uint64(count as u64)
+                })
+            }
+            ("Package", "depth") => {
+                let depths = self.depths();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_package().unwrap();
+                    match depths.get(&package.id) {
+                        Some(d) => FieldValue::Uint64(*d as u64),
+                        None => FieldValue::Null,
+                    }
+                })
+            }
+            ("DependencyPath", "length") => resolve_property_with(
+                contexts,
+                accessor_property!(as_dependency_path, length, {
+                    FieldValue::Uint64(length as u64)
+                }),
+            ),
+            ("DependencyPath", "names") => resolve_property_with(
+                contexts,
+                accessor_property!(as_dependency_path, names),
+            ),
+            ("Feature", "name") => resolve_property_with(
+                contexts,
+                accessor_property!(as_feature, name),
+            ),
+            ("Feature", "enabled") => resolve_property_with(
+                contexts,
+                accessor_property!(as_feature, enabled),
+            ),
+            ("Feature", "isDefault") => resolve_property_with(
+                contexts,
+                accessor_property!(as_feature, is_default),
+            ),
+            ("Source", "kind") => resolve_property_with(contexts, |v| {
+                v.as_source().unwrap().kind().as_str().into()
+            }),
+            ("Source", "url") => resolve_property_with(
+                contexts,
+                accessor_property!(as_source, url),
+            ),
+            ("Source", "rev") => resolve_property_with(
+                contexts,
+                accessor_property!(as_source, rev),
+            ),
+            ("Source", "branch") => resolve_property_with(
+                contexts,
+                accessor_property!(as_source, branch),
+            ),
+            ("Source", "tag") => resolve_property_with(
+                contexts,
+                accessor_property!(as_source, tag),
+            ),
+            ("Source", "lockedCommit") => resolve_property_with(
+                contexts,
+                accessor_property!(as_source, locked_commit),
+            ),
+            ("Source", "isCratesIo") => resolve_property_with(
+                contexts,
+                accessor_property!(as_source, is_crates_io),
+            ),
+            ("DuplicateGroup", "name") => resolve_property_with(
+                contexts,
+                accessor_property!(as_duplicate_group, name),
+            ),
+            ("DuplicateGroup", "versions") => resolve_property_with(
+                contexts,
+                accessor_property!(as_duplicate_group, versions),
+            ),
+            ("LicenseExpression", "expression") => resolve_property_with(
+                contexts,
+                accessor_property!(as_license_expression, raw),
+            ),
+            ("LicenseExpression", "isValidSpdx") => resolve_property_with(
+                contexts,
+                accessor_property!(as_license_expression, is_valid_spdx),
+            ),
+            ("LicenseExpression", "satisfiesAllowList") => {
+                resolve_property_with(
+                    contexts,
+                    accessor_property!(
+                        as_license_expression,
+                        satisfies_allow_list
+                    ),
+                )
+            }
+            ("License", "id") => resolve_property_with(
+                contexts,
+                accessor_property!(as_license, id),
+            ),
+            ("License", "name") => resolve_property_with(
+                contexts,
+                accessor_property!(as_license, full_name),
+            ),
+            ("License", "isOsiApproved") => resolve_property_with(
+                contexts,
+                accessor_property!(as_license, is_osi_approved),
+            ),
+            ("License", "isFsfLibre") => resolve_property_with(
+                contexts,
+                accessor_property!(as_license, is_fsf_libre),
+            ),
+            ("License", "isCopyleft") => resolve_property_with(
+                contexts,
+                accessor_property!(as_license, is_copyleft),
+            ),
+            ("License", "category") => resolve_property_with(contexts, |v| {
+                v.as_license().unwrap().category().as_str().into()
+            }),
+            ("LicenseExpression", "isCompatibleWithRoot") => {
+                resolve_property_with(
+                    contexts,
+                    accessor_property!(
+                        as_license_expression,
+                        is_compatible_with_root
+                    ),
+                )
+            }
+            ("License", "isDeprecated") => resolve_property_with(
+                contexts,
+                accessor_property!(as_license, is_deprecated),
+            ),
+            ("LicenseFile", "path") => resolve_property_with(contexts, |v| {
+                FieldValue::String(
+                    v.as_license_file()
+                        .unwrap()
+                        .path()
+                        .to_string_lossy()
+                        .into(),
+                )
+            }),
+            ("LicenseFile", "confidence") => {
+                resolve_property_with(contexts, |v| {
+                    match v.as_license_file().unwrap().confidence() {
+                        Some(c) => FieldValue::Float64(c),
+                        None => FieldValue::Null,
+                    }
+                })
+            }
+            ("Target", "name") => resolve_property_with(
+                contexts,
+                field_property!(as_target, name),
+            ),
+            ("Target", "kind") => resolve_property_with(
+                contexts,
+                field_property!(as_target, kind),
+            ),
+            ("Target", "crateTypes") => resolve_property_with(
+                contexts,
+                field_property!(as_target, crate_types),
+            ),
+            ("Target", "srcPath") => resolve_property_with(
+                contexts,
+                field_property!(as_target, src_path, {
+                    src_path.to_string().into()
+                }),
+            ),
+            ("Target", "edition") => resolve_property_with(
+                contexts,
+                field_property!(as_target, edition, {
+                    edition.as_str().into()
+                }),
+            ),
+            ("Target", "requiredFeatures") => resolve_property_with(
+                contexts,
+                field_property!(as_target, required_features),
+            ),
+            ("DependencyEdge", "kind") => resolve_property_with(
+                contexts,
+                accessor_property!(as_dependency_edge, kind, {
+                    kind.to_string().into()
+                }),
+            ),
+            ("DependencyEdge", "target") => resolve_property_with(
+                contexts,
+                accessor_property!(as_dependency_edge, target, {
+                    match target {
+                        Some(t) => t.to_string().into(),
+                        None => FieldValue::Null,
+                    }
+                }),
+            ),
+            ("DependencyEdge", "optional") => resolve_property_with(
+                contexts,
+                accessor_property!(as_dependency_edge, optional),
+            ),
+            ("DependencyEdge", "usesDefaultFeatures") => resolve_property_with(
+                contexts,
+                accessor_property!(as_dependency_edge, uses_default_features),
+            ),
+            ("DependencyEdge", "enabledFeatures") => resolve_property_with(
+                contexts,
+                accessor_property!(as_dependency_edge, enabled_features, {
+                    enabled_features.to_vec().into()
+                }),
+            ),
+            ("DependencyEdge", "renamedAs") => resolve_property_with(
+                contexts,
+                accessor_property!(as_dependency_edge, renamed_as),
+            ),
+            ("CratesIoStats", "totalDownloads") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.total_downloads(&nv.name) {
+                            Some(n) => FieldValue::Uint64(n),
+                            None => FieldValue::Null,
+                        }
+                    })
+                })
+            }
+            ("CratesIoStats", "recentDownloads") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.recent_downloads(&nv.name) {
+                            Some(n) => FieldValue::Uint64(n),
+                            None => FieldValue::Null,
+                        }
+                    })
+                })
+            }
+            ("CratesIoStats", "versionDownloads") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.version_downloads(nv) {
+                            Some(n) => FieldValue::Uint64(n),
+                            None => FieldValue::Null,
+                        }
+                    })
+                })
+            }
+            ("CratesIoStats", "versionsCount") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.versions_count(&nv.name) {
+                            Some(n) => FieldValue::Uint64(n as u64),
+                            None => FieldValue::Null,
+                        }
+                    })
+                })
+            }
+            ("CratesIoStats", "yanked") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.yanked(nv) {
+                            Some(b) => b.into(),
+                            None => FieldValue::Null,
+                        }
+                    })
+                })
+            }
+            ("CratesIoStats", "yankedVersions") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.yanked_versions(&nv.name) {
+                            Some(v) => v.into(),
+                            None => FieldValue::Null,
+                        }
+                    })
+                })
+            }
+            ("CratesIoStats", "yankedVersionsCount") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.yanked_versions_count(&nv.name) {
+                            Some(n) => FieldValue::Uint64(n as u64),
+                            None => FieldValue::Null,
+                        }
+                    })
+                })
+            }
+            ("CratesIoStats", "yankedRatio") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.yanked_ratio(&nv.name) {
+                            Some(n) => FieldValue::Float64(n),
+                            None => FieldValue::Null,
+                        }
+                    })
+                })
+            }
+            ("CratesIoStats", "latestVersion") => {
+                let registries = self.registries();
                 resolve_property_with(contexts, move |v| {
-                    let nv = v.as_crates_io_stats().unwrap();
-                    match crates_io_client
-                        .borrow_mut()
-                        .total_downloads(&nv.name)
-                    {
-                        Some(n) => FieldValue::Uint64(n),
-                        None => FieldValue::Null,
-                    }
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.latest_version(&nv.name) {
+                            Some(v) => FieldValue::String(v.to_string()),
+                            None => FieldValue::Null,
+                        }
+                    })
                 })
             }
-            ("CratesIoStats", "recentDownloads") => {
-                let crates_io_client = self.crates_io_client();
+            ("CratesIoStats", "latestStableVersion") => {
+                let registries = self.registries();
                 resolve_property_with(contexts, move |v| {
-                    let nv = v.as_crates_io_stats().unwrap();
-                    match crates_io_client
-                        .borrow_mut()
-                        .recent_downloads(&nv.name)
-                    {
-                        Some(n) => FieldValue::Uint64(n),
-                        None => FieldValue::Null,
-                    }
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.latest_stable_version(&nv.name) {
+                            Some(v) => FieldValue::String(v.to_string()),
+                            None => FieldValue::Null,
+                        }
+                    })
                 })
             }
-            ("CratesIoStats", "versionDownloads") => {
-                let crates_io_client = self.crates_io_client();
+            ("CratesIoStats", "isOutdated") => {
+                let registries = self.registries();
                 resolve_property_with(contexts, move |v| {
-                    let nv = v.as_crates_io_stats().unwrap();
-                    match crates_io_client.borrow_mut().version_downloads(nv) {
-                        Some(n) => FieldValue::Uint64(n),
-                        None => FieldValue::Null,
-                    }
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.is_outdated(nv) {
+                            Some(b) => b.into(),
+                            None => FieldValue::Null,
+                        }
+                    })
                 })
             }
-            ("CratesIoStats", "versionsCount") => {
-                let crates_io_client = self.crates_io_client();
+            ("CratesIoStats", "majorVersionsBehind") => {
+                let registries = self.registries();
                 resolve_property_with(contexts, move |v| {
-                    let nv = v.as_crates_io_stats().unwrap();
-                    match crates_io_client.borrow_mut().versions_count(&nv.name)
-                    {
-                        Some(n) => FieldValue::Uint64(n as u64),
-                        None => FieldValue::Null,
-                    }
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.major_versions_behind(nv) {
+                            Some(n) => FieldValue::Uint64(n as u64),
+                            None => FieldValue::Null,
+                        }
+                    })
                 })
             }
-            ("CratesIoStats", "yanked") => {
-                let crates_io_client = self.crates_io_client();
+            ("CratesIoStats", "daysSinceLockedVersionReleased") => {
+                let registries = self.registries();
                 resolve_property_with(contexts, move |v| {
-                    let nv = v.as_crates_io_stats().unwrap();
-                    match crates_io_client.borrow_mut().yanked(nv) {
-                        Some(b) => b.into(),
-                        None => FieldValue::Null,
-                    }
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.days_since_version_released(nv) {
+                            Some(n) => FieldValue::Int64(n),
+                            None => FieldValue::Null,
+                        }
+                    })
                 })
             }
-            ("CratesIoStats", "yankedVersions") => {
-                let crates_io_client = self.crates_io_client();
+            ("CratesIoStats", "daysSinceLatestRelease") => {
+                let registries = self.registries();
                 resolve_property_with(contexts, move |v| {
-                    let nv = v.as_crates_io_stats().unwrap();
-                    match crates_io_client
-                        .borrow_mut()
-                        .yanked_versions(&nv.name)
-                    {
-                        Some(v) => v.into(),
-                        None => FieldValue::Null,
-                    }
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.days_since_latest_release(&nv.name) {
+                            Some(n) => FieldValue::Int64(n),
+                            None => FieldValue::Null,
+                        }
+                    })
                 })
             }
-            ("CratesIoStats", "yankedVersionsCount") => {
-                let crates_io_client = self.crates_io_client();
+            ("CratesIoStats", "maxVersion") => {
+                let registries = self.registries();
                 resolve_property_with(contexts, move |v| {
-                    let nv = v.as_crates_io_stats().unwrap();
-                    match crates_io_client
-                        .borrow_mut()
-                        .yanked_versions_count(&nv.name)
-                    {
-                        Some(n) => FieldValue::Uint64(n as u64),
-                        None => FieldValue::Null,
-                    }
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client
+                            .crate_data(&nv.name)
+                            .map(|c| c.max_version.clone())
+                            .into()
+                    })
                 })
             }
-            ("CratesIoStats", "yankedRatio") => {
-                let crates_io_client = self.crates_io_client();
+            ("CratesIoStats", "reverseDependenciesCount") => {
+                let registries = self.registries();
                 resolve_property_with(contexts, move |v| {
-                    let nv = v.as_crates_io_stats().unwrap();
-                    match crates_io_client.borrow_mut().yanked_ratio(&nv.name) {
-                        Some(n) => FieldValue::Float64(n),
-                        None => FieldValue::Null,
-                    }
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client.reverse_dependencies_count(&nv.name).into()
+                    })
+                })
+            }
+            ("CratesIoStats", "unixCreatedAt") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client
+                            .crate_data(&nv.name)
+                            .map(|c| c.created_at.timestamp())
+                            .into()
+                    })
+                })
+            }
+            ("CratesIoStats", "unixUpdatedAt") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client
+                            .crate_data(&nv.name)
+                            .map(|c| c.updated_at.timestamp())
+                            .into()
+                    })
+                })
+            }
+            ("CratesIoStats", "unixLockedVersionPublishedAt") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client
+                            .version(nv)
+                            .map(|v| v.created_at.timestamp())
+                            .into()
+                    })
+                })
+            }
+            ("CratesIoStats", "lockedVersionCrateSize") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client.version(nv).and_then(|v| v.crate_size).into()
+                    })
+                })
+            }
+            ("CratesIoStats", "lockedVersionLicense") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client
+                            .version(nv)
+                            .and_then(|v| v.license.clone())
+                            .into()
+                    })
+                })
+            }
+            ("CratesIoStats", "description") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client
+                            .crate_data(&nv.name)
+                            .and_then(|c| c.description.clone())
+                            .into()
+                    })
+                })
+            }
+            ("CratesIoStats", "homepage") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client
+                            .crate_data(&nv.name)
+                            .and_then(|c| c.homepage.clone())
+                            .into()
+                    })
+                })
+            }
+            ("CratesIoStats", "documentationUrl") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client
+                            .crate_data(&nv.name)
+                            .and_then(|c| c.documentation.clone())
+                            .into()
+                    })
+                })
+            }
+            ("CratesIoStats", "publisherChangedSinceVersion") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        client.publisher_changed_since(nv).into()
+                    })
+                })
+            }
+            ("CratesIoStats", "downloadTrendSlope") => {
+                let registries = self.registries();
+                resolve_property_with(contexts, move |v| {
+                    let package = v.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        match client.download_trend_slope(&nv.name) {
+                            Some(n) => FieldValue::Float64(n),
+                            None => FieldValue::Null,
+                        }
+                    })
                 })
             }
+            ("PublisherHistory", "version") => resolve_property_with(
+                contexts,
+                field_property!(as_publisher_history, version, {
+                    version.num.clone().into()
+                }),
+            ),
+            ("PublisherHistory", "unixPublishedAt") => resolve_property_with(
+                contexts,
+                field_property!(as_publisher_history, version, {
+                    version.created_at.timestamp().into()
+                }),
+            ),
+            ("PublisherHistory", "yanked") => resolve_property_with(
+                contexts,
+                field_property!(as_publisher_history, version, {
+                    version.yanked.into()
+                }),
+            ),
+            ("PublisherHistory", "isNewPublisher") => resolve_property_with(
+                contexts,
+                field_property!(as_publisher_history, is_new_publisher),
+            ),
+            ("DownloadPoint", "date") => resolve_property_with(
+                contexts,
+                field_property!(as_download_point, date, {
+                    date.to_string().into()
+                }),
+            ),
+            ("DownloadPoint", "downloads") => resolve_property_with(
+                contexts,
+                field_property!(as_download_point, downloads),
+            ),
+            ("CratesIoUser", "login") => resolve_property_with(
+                contexts,
+                field_property!(as_crates_io_user, login),
+            ),
+            ("CratesIoUser", "name") => resolve_property_with(
+                contexts,
+                field_property!(as_crates_io_user, name),
+            ),
+            ("CratesIoUser", "kind") => resolve_property_with(contexts, |v| {
+                user_kind(v.as_crates_io_user().unwrap()).into()
+            }),
+            ("CratesIoUser", "url") => resolve_property_with(
+                contexts,
+                field_property!(as_crates_io_user, url),
+            ),
             ("Webpage" | "Repository" | "GitHubRepository", "url") => {
                 resolve_property_with(contexts, |v| match v.as_webpage() {
                     Some(url) => FieldValue::String(url.to_owned()),
@@ -766,7 +1633,10 @@ impl<'a> BasicAdapter<'a> for IndicateAdapter {
                 // Must be done here to ensure they live long enough (and are
                 // not lazily evaluated)
                 let packages = self.packages();
-                let direct_dependencies = self.direct_dependencies();
+                let direct_dependencies = Self::dependency_kinds(parameters)
+                    .into_iter()
+                    .map(|k| self.direct_dependencies(k))
+                    .collect::<Vec<_>>();
                 resolve_neighbors_with(contexts, move |vertex| {
                     // This is in fact a Package, otherwise it would be `None`
                     // First get all dependencies, and then resolve their package
@@ -774,17 +1644,93 @@ impl<'a> BasicAdapter<'a> for IndicateAdapter {
                     let package = vertex.as_package().unwrap();
                     Self::get_dependencies(
                         Rc::clone(&packages),
-                        &Rc::clone(&direct_dependencies),
+                        &direct_dependencies,
+                        &package.id,
+                    )
+                })
+            }
+            ("Package", "dependents") => {
+                let packages = self.packages();
+                let dependents = Self::dependency_kinds(parameters)
+                    .into_iter()
+                    .map(|k| self.dependents(k))
+                    .collect::<Vec<_>>();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_package().unwrap();
+                    // The dependent map has the same shape as the dependency
+                    // map, so it can be resolved the same way
+                    Self::get_dependencies(
+                        Rc::clone(&packages),
+                        &dependents,
                         &package.id,
                     )
                 })
             }
+            ("Package", "dependencyEdges") => {
+                let target = parameters
+                    .get("activeOnTarget")
+                    .and_then(FieldValue::as_str)
+                    .map(|t| {
+                        get_builtin_target_by_triple(t).unwrap_or_else(|| {
+                            panic!("unknown activeOnTarget parameter: {t}")
+                        })
+                    });
+                let dependency_edges = self.dependency_edges();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_package().unwrap();
+                    let mut edges = dependency_edges
+                        .get(&package.id)
+                        .map(|e| e.to_vec())
+                        .unwrap_or_default();
+                    if let Some(t) = target {
+                        edges.retain(|e| e.active_on_target(t));
+                    }
+                    Box::new(edges.into_iter().map(Vertex::DependencyEdge))
+                })
+            }
             ("Package", "cratesIo") => resolve_neighbors_with(contexts, |v| {
                 let package = v.as_package().unwrap();
+
+                // A package with the same name on crates.io may be a
+                // completely different package, so all stats are null
                 Box::new(std::iter::once(Vertex::CratesIoStats(
-                    NameVersion::from(package),
+                    RegistryPackage {
+                        name_version: NameVersion::from(package),
+                        registry: None,
+                        is_from_registry: Source::from(package.as_ref())
+                            .is_crates_io(),
+                    },
                 )))
             }),
+            ("Package", "registryStats") => {
+                let registries = self.registries();
+                resolve_neighbors_with(contexts, move |v| {
+                    let package = v.as_package().unwrap();
+                    let source = Source::from(package.as_ref());
+
+                    // Packages from registries that are not configured may
+                    // also be completely different packages on crates.io
+                    let registry = match source.url() {
+                        _ if source.is_crates_io() => None,
+                        Some(url) if source.kind() == SourceKind::Registry => {
+                            let key = registry_key(url);
+                            if !registries.is_configured(&key) {
+                                return Box::new(std::iter::empty());
+                            }
+                            Some(key)
+                        }
+                        _ => return Box::new(std::iter::empty()),
+                    };
+
+                    Box::new(std::iter::once(Vertex::CratesIoStats(
+                        RegistryPackage {
+                            name_version: NameVersion::from(package),
+                            registry,
+                            is_from_registry: true,
+                        },
+                    )))
+                })
+            }
             ("Package", "repository") => {
                 let gh_client = self.gh_client();
                 resolve_neighbors_with(contexts, move |v| {
@@ -811,6 +1757,12 @@ impl<'a> BasicAdapter<'a> for IndicateAdapter {
 
                 resolve_neighbors_with(contexts, move |vertex| {
                     let package = vertex.as_package().unwrap();
+
+                    // RustSec only tracks advisories for crates.io packages
+                    if !Source::from(package.as_ref()).is_crates_io() {
+                        return Box::new(std::iter::empty());
+                    }
+
                     let include_withdrawn = include_withdrawn
                         .clone()
                         .expect("includeWithdrawn parameter required but not provided")
@@ -871,8 +1823,7 @@ impl<'a> BasicAdapter<'a> for IndicateAdapter {
                 let geiger_client = self.geiger_client();
                 resolve_neighbors_with(contexts, move |vertex| {
                     let package = vertex.as_package().unwrap();
-                    let gid = package.into();
-                    let unsafety = geiger_client.unsafety(&gid);
+                    let unsafety = geiger_client.unsafety(package);
 
                     if let Some(u) = unsafety {
                         Box::new(std::iter::once(Vertex::GeigerUnsafety(u)))
@@ -948,6 +1899,432 @@ impl<'a> BasicAdapter<'a> for IndicateAdapter {
                     )
                 })
             }
+            ("Package", "features") => {
+                let enabled_features = self.enabled_features();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_package().unwrap();
+                    let mut names = package.features.keys().collect::<Vec<_>>();
+                    // Features are stored in a map, so sort for stable output
+                    names.sort();
+                    let features = names
+                        .into_iter()
+                        .map(|name| {
+                            Vertex::Feature(Rc::new(Self::get_feature(
+                                &enabled_features,
+                                Rc::clone(package),
+                                name,
+                            )))
+                        })
+                        .collect::<Vec<_>>();
+                    Box::new(features.into_iter())
+                })
+            }
+            ("Package", "source") => resolve_neighbors_with(contexts, |v| {
+                let package = v.as_package().unwrap();
+                Box::new(std::iter::once(Vertex::Source(Rc::new(
+                    Source::from(package.as_ref()),
+                ))))
+            }),
+            ("Package", "licenseExpression") => {
+                let allowed = parameters
+                    .get("allowed")
+                    .and_then(|a| a.as_vec_with(FieldValue::as_str))
+                    .map(|a| {
+                        a.into_iter().map(String::from).collect::<Vec<_>>()
+                    });
+                // Outer `None` if there is no root license to compare with,
+                // inner `None` if the root package has no license
+                let root_license = match parameters
+                    .get("rootLicense")
+                    .and_then(FieldValue::as_str)
+                {
+                    Some(l) => Some(Some(l.to_string())),
+                    None => {
+                        self.metadata.root_package().map(|p| p.license.clone())
+                    }
+                };
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_package().unwrap();
+                    match &package.license {
+                        Some(l) => {
+                            let mut expression =
+                                LicenseExpression::new(l, allowed.clone());
+                            if let Some(r) = &root_license {
+                                expression =
+                                    expression.with_root_license(r.as_deref());
+                            }
+                            Box::new(std::iter::once(
+                                Vertex::LicenseExpression(Rc::new(expression)),
+                            ))
+                        }
+                        None => Box::new(std::iter::empty()),
+                    }
+                })
+            }
+            ("Package", "licenseFiles") => {
+                resolve_neighbors_with(contexts, |vertex| {
+                    let package = vertex.as_package().unwrap();
+                    let package_dir = util::local_package_path(package);
+                    let license_file = package.license_file.as_ref().map(|l| {
+                        l.as_std_path()
+                            .strip_prefix(&package_dir)
+                            .unwrap_or(l.as_std_path())
+                    });
+                    let license_files = LicenseFile::from_package_dir(
+                        &package_dir,
+                        license_file,
+                    );
+                    Box::new(
+                        license_files
+                            .into_iter()
+                            .map(|l| Vertex::LicenseFile(Rc::new(l))),
+                    )
+                })
+            }
+            ("LicenseFile", "detectedLicense") => {
+                resolve_neighbors_with(contexts, |vertex| {
+                    let license_file = vertex.as_license_file().unwrap();
+                    Box::new(
+                        license_file
+                            .detected_license()
+                            .into_iter()
+                            .map(Vertex::License),
+                    )
+                })
+            }
+            ("LicenseExpression", "licenses") => {
+                resolve_neighbors_with(contexts, |vertex| {
+                    let expression = vertex.as_license_expression().unwrap();
+                    Box::new(
+                        expression.licenses().into_iter().map(Vertex::License),
+                    )
+                })
+            }
+            ("Package", "otherVersionsInGraph") => {
+                let packages_by_name = self.packages_by_name();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_package().unwrap();
+                    let others = packages_by_name
+                        .get(&package.name)
+                        .map(|packages| {
+                            packages
+                                .iter()
+                                // The same version may come from another
+                                // source, which is not another version
+                                .filter(|p| p.version != package.version)
+                                .map(|p| Vertex::Package(Rc::clone(p)))
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    Box::new(others.into_iter())
+                })
+            }
+            ("Package", "pathsFromRoot") => {
+                let max_paths = parameters
+                    .get("maxPaths")
+                    .and_then(FieldValue::as_usize)
+                    .unwrap_or_else(|| {
+                        panic!("maxPaths must be a non-negative integer")
+                    });
+                let packages = self.packages();
+                let dependents = self.dependents(DependencyKind::Normal);
+                let depths = self.depths();
+                let root_ids = self.root_package_ids();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_package().unwrap();
+                    let paths = util::paths_from_roots(
+                        &dependents,
+                        &depths,
+                        &root_ids,
+                        &package.id,
+                        max_paths,
+                    )
+                    .into_iter()
+                    .map(|ids| {
+                        let path_packages = ids
+                            .iter()
+                            .map(|id| Rc::clone(packages.get(id).unwrap()))
+                            .collect();
+                        Vertex::DependencyPath(Rc::new(DependencyPath::new(
+                            path_packages,
+                        )))
+                    })
+                    .collect::<Vec<_>>();
+                    Box::new(paths.into_iter())
+                })
+            }
+            ("DependencyPath", "packages") => {
+                resolve_neighbors_with(contexts, |vertex| {
+                    let path = vertex.as_dependency_path().unwrap();
+                    let packages = path
+                        .packages()
+                        .iter()
+                        .map(|p| Vertex::Package(Rc::clone(p)))
+                        .collect::<Vec<_>>();
+                    Box::new(packages.into_iter())
+                })
+            }
+            ("DuplicateGroup", "packages") => {
+                resolve_neighbors_with(contexts, |vertex| {
+                    let group = vertex.as_duplicate_group().unwrap();
+                    let packages = group
+                        .packages()
+                        .iter()
+                        .map(|p| Vertex::Package(Rc::clone(p)))
+                        .collect::<Vec<_>>();
+                    Box::new(packages.into_iter())
+                })
+            }
+            ("Package", "targets") => resolve_neighbors_with(contexts, |v| {
+                let package = v.as_package().unwrap();
+                let targets = package
+                    .targets
+                    .iter()
+                    .map(|t| Vertex::Target(Rc::new(t.clone())))
+                    .collect::<Vec<_>>();
+                Box::new(targets.into_iter())
+            }),
+            ("Feature", "package") => {
+                resolve_neighbors_with(contexts, |vertex| {
+                    let feature = vertex.as_feature().unwrap();
+                    Box::new(std::iter::once(Vertex::Package(
+                        feature.package(),
+                    )))
+                })
+            }
+            ("Feature", "activates") => {
+                let enabled_features = self.enabled_features();
+                let dependency_edges = self.dependency_edges();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let feature = vertex.as_feature().unwrap();
+                    let package = feature.package();
+                    let mut activated = Vec::new();
+
+                    for value in feature.values() {
+                        match value {
+                            FeatureValue::Feature(name) => {
+                                activated.push(Self::get_feature(
+                                    &enabled_features,
+                                    Rc::clone(&package),
+                                    name,
+                                ));
+                            }
+                            FeatureValue::Dependency(_) => {
+                                // Handled by `activatesDependencies`
+                            }
+                            FeatureValue::DependencyFeature {
+                                dependency,
+                                feature,
+                                weak,
+                            } => {
+                                // A non-weak dependency feature also enables
+                                // the (possibly implicit) feature of an
+                                // optional dependency
+                                if !weak
+                                    && package.features.contains_key(dependency)
+                                {
+                                    activated.push(Self::get_feature(
+                                        &enabled_features,
+                                        Rc::clone(&package),
+                                        dependency,
+                                    ));
+                                }
+
+                                if let Some(edge) = Self::get_feature_dependency(
+                                    &dependency_edges,
+                                    &package,
+                                    dependency,
+                                ) {
+                                    activated.push(Self::get_feature(
+                                        &enabled_features,
+                                        edge.package(),
+                                        feature,
+                                    ));
+                                }
+                            }
+                        }
+                    }
+
+                    Box::new(
+                        activated
+                            .into_iter()
+                            .map(|f| Vertex::Feature(Rc::new(f))),
+                    )
+                })
+            }
+            ("Feature", "activatesDependencies") => {
+                let dependency_edges = self.dependency_edges();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let feature = vertex.as_feature().unwrap();
+                    let package = feature.package();
+                    let mut activated: Vec<Rc<Package>> = Vec::new();
+
+                    for value in feature.values() {
+                        let dependency = match value {
+                            FeatureValue::Dependency(dependency)
+                            | FeatureValue::DependencyFeature {
+                                dependency,
+                                weak: false,
+                                ..
+                            } => dependency,
+                            _ => continue,
+                        };
+
+                        if let Some(edge) = Self::get_feature_dependency(
+                            &dependency_edges,
+                            &package,
+                            dependency,
+                        ) {
+                            let p = edge.package();
+                            if edge.optional()
+                                && !activated.iter().any(|a| a.id == p.id)
+                            {
+                                activated.push(p);
+                            }
+                        }
+                    }
+
+                    Box::new(activated.into_iter().map(Vertex::Package))
+                })
+            }
+            ("DependencyEdge", "package") => {
+                resolve_neighbors_with(contexts, |vertex| {
+                    let edge = vertex.as_dependency_edge().unwrap();
+                    Box::new(std::iter::once(Vertex::Package(edge.package())))
+                })
+            }
+            ("CratesIoStats", "owners") => {
+                let registries = self.registries();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        let owners = client
+                            .owners(&nv.name)
+                            .cloned()
+                            .unwrap_or_default();
+                        Box::new(
+                            owners
+                                .into_iter()
+                                .map(|u| Vertex::CratesIoUser(Rc::new(u))),
+                        )
+                    })
+                })
+            }
+            ("CratesIoStats", "publishedBy") => {
+                let registries = self.registries();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        let user = client.published_by(nv);
+                        Box::new(
+                            user.into_iter()
+                                .map(|u| Vertex::CratesIoUser(Rc::new(u))),
+                        )
+                    })
+                })
+            }
+            ("CratesIoStats", "publisherHistory") => {
+                let since_version = parameters
+                    .get("sinceVersion")
+                    .and_then(FieldValue::as_str)
+                    .map(|s| {
+                        rustsec::Version::parse(s).unwrap_or_else(|e| {
+                            panic!("invalid sinceVersion parameter: {s} ({e})")
+                        })
+                    });
+                let registries = self.registries();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_crates_io_stats().unwrap();
+                    registries.with_client(package, |client, nv| {
+                        let history = client
+                            .publisher_history(&nv.name)
+                            .unwrap_or_default();
+
+                        // Versions are in publishing order, so skip everything up
+                        // until and including the given version
+                        let skip = match &since_version {
+                            Some(since) => history
+                                .iter()
+                                .position(|e| {
+                                    rustsec::Version::parse(&e.version.num)
+                                        .is_ok_and(|v| v == *since)
+                                })
+                                .map_or(history.len(), |i| i + 1),
+                            None => 0,
+                        };
+                        Box::new(
+                            history
+                                .into_iter()
+                                .skip(skip)
+                                .map(|e| Vertex::PublisherHistory(Rc::new(e))),
+                        )
+                    })
+                })
+            }
+            ("CratesIoStats", "downloadHistory") => {
+                // crates.io keeps no more than this many days
+                let days = parameters
+                    .get("days")
+                    .and_then(|d| {
+                        if d == &FieldValue::Null {
+                            None
+                        } else {
+                            Some(d.as_usize().unwrap_or_else(|| {
+                                panic!("days must be a non-negative integer, got {d:?}")
+                            }))
+                        }
+                    })
+                    .map_or(DOWNLOAD_HISTORY_DAYS, |d| {
+                        d.min(DOWNLOAD_HISTORY_DAYS)
+                    });
+                let locked_version_only = parameters
+                    .get("lockedVersionOnly")
+                    .and_then(FieldValue::as_bool)
+                    .unwrap_or(false);
+                let registries = self.registries();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let package = vertex.as_crates_io_stats().unwrap();
+                    let history =
+                        registries.with_client(package, |client, nv| {
+                            client
+                                .download_history(nv, days, locked_version_only)
+                                .unwrap_or_default()
+                        });
+                    Box::new(history.into_iter().map(Vertex::DownloadPoint))
+                })
+            }
+            ("PublisherHistory", "publishedBy") => {
+                resolve_neighbors_with(contexts, |vertex| {
+                    let entry = vertex.as_publisher_history().unwrap();
+                    Box::new(
+                        entry
+                            .version
+                            .published_by
+                            .clone()
+                            .into_iter()
+                            .map(|u| Vertex::CratesIoUser(Rc::new(u))),
+                    )
+                })
+            }
+            ("CratesIoUser", "gitHubUser") => {
+                let gh_client = self.gh_client();
+                resolve_neighbors_with(contexts, move |vertex| {
+                    let user = vertex.as_crates_io_user().unwrap();
+
+                    // crates.io users are always GitHub users, but teams use
+                    // logins like `github:org:team`
+                    if user_kind(user) != "user" {
+                        return Box::new(std::iter::empty());
+                    }
+
+                    match gh_client.borrow_mut().get_public_user(&user.login) {
+                        Some(u) => Box::new(std::iter::once(
+                            Vertex::GitHubUser(Arc::clone(&u)),
+                        )),
+                        None => Box::new(std::iter::empty()),
+                    }
+                })
+            }
             ("GitHubRepository", "owner") => {
                 let gh_client = self.gh_client();
                 resolve_neighbors_with(contexts, move |vertex| {
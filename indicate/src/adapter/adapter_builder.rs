@@ -1,11 +1,22 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 use cargo_metadata::{CargoOpt, Metadata};
 use once_cell::unsync::OnceCell;
 
 use crate::{
-    advisory::AdvisoryClient, crates_io::CratesIoClient, geiger::GeigerClient,
-    repo::github::GitHubClient, ManifestPath,
+    advisory::AdvisoryClient,
+    crates_io::CratesIoClient,
+    errors::LockfileError,
+    geiger::GeigerClient,
+    lockfile::{default_cargo_home, metadata_from_lockfile},
+    registry::{registries_from_cargo_config, RegistryClients, RegistryConfig},
+    registry_index::RegistryIndexClient,
+    repo::github::GitHubClient,
+    ManifestPath,
 };
 
 use super::IndicateAdapter;
@@ -14,11 +25,15 @@ use super::IndicateAdapter;
 pub struct IndicateAdapterBuilder {
     manifest_path: ManifestPath,
     features: Vec<CargoOpt>,
+    target_platform: Option<String>,
     metadata: Option<Metadata>,
     github_client: Option<GitHubClient>,
     advisory_client: Option<AdvisoryClient>,
     geiger_client: Option<GeigerClient>,
     crates_io_client: Option<CratesIoClient>,
+    crates_io_db_dump: Option<PathBuf>,
+    registry_index: Option<RegistryIndexClient>,
+    registries: Vec<RegistryConfig>,
 }
 
 impl IndicateAdapterBuilder {
@@ -33,14 +48,60 @@ impl IndicateAdapterBuilder {
         Self {
             manifest_path,
             features: Vec::new(),
+            target_platform: None,
             metadata: None,
             github_client: None,
             advisory_client: None,
             geiger_client: None,
             crates_io_client: None,
+            crates_io_db_dump: None,
+            registry_index: None,
+            registries: Vec::new(),
         }
     }
 
+    /// Creates a new builder for a [`IndicateAdapter`] using a `Cargo.lock`
+    /// file instead of running `cargo metadata`
+    ///
+    /// Manifests of packages already downloaded to `~/.cargo/registry/src` or
+    /// `~/.cargo/git/checkouts` (or the same directories in `$CARGO_HOME`) are
+    /// used to provide information not available in the lockfile, see
+    /// [`crate::lockfile`] for what is lost otherwise. Since metadata is set
+    /// explicitly, features and a target platform cannot be used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant if the lockfile cannot be read or parsed.
+    pub fn from_lockfile(
+        lockfile_path: &Path,
+    ) -> Result<IndicateAdapterBuilder, Box<LockfileError>> {
+        let metadata = metadata_from_lockfile(
+            lockfile_path,
+            default_cargo_home().as_deref(),
+        )?;
+        Ok(Self::from_metadata(metadata))
+    }
+
+    /// Creates a new builder for a [`IndicateAdapter`] using metadata that
+    /// has already been generated, such as saved output from
+    /// `cargo metadata --format-version 1`
+    ///
+    /// The manifest path is the one of the root package described by the
+    /// metadata, or the workspace manifest for virtual workspaces. It does not
+    /// need to exist on this machine, but features relying on the package
+    /// source code will not work without it.
+    #[must_use]
+    pub fn from_metadata(metadata: Metadata) -> IndicateAdapterBuilder {
+        let root_manifest_path = metadata
+            .root_package()
+            .map(|p| p.manifest_path.clone().into_std_path_buf())
+            .unwrap_or_else(|| {
+                metadata.workspace_root.join("Cargo.toml").into()
+            });
+        Self::new(ManifestPath::unchecked(root_manifest_path))
+            .metadata(metadata)
+    }
+
     /// Will build the [`IndicateAdapter`]
     ///
     /// If metadata is not explicitly set, one will be generated using the
@@ -48,17 +109,28 @@ impl IndicateAdapterBuilder {
     ///
     /// # Panics
     ///
-    /// Panics if both features and metadata have been set manually.
+    /// Panics if both features or a target platform and metadata have been
+    /// set manually.
     #[must_use]
     pub fn build(self) -> IndicateAdapter {
         assert!(
             self.features.is_empty() || self.metadata.is_none(),
             "features and metadata both set explicitly at the same time"
         );
+        assert!(
+            self.target_platform.is_none() || self.metadata.is_none(),
+            "target platform and metadata both set explicitly at the same time"
+        );
 
-        let metadata = match self.metadata {
-            Some(m) => m,
-            None => self
+        let metadata = match (self.metadata, &self.target_platform) {
+            (Some(m), _) => m,
+            (None, Some(t)) => self
+                .manifest_path
+                .metadata_for_target(self.features.clone(), t)
+                .unwrap_or_else(|e| {
+                    panic!("could not generate metadata due to error: {e}")
+                }),
+            (None, None) => self
                 .manifest_path
                 .metadata(self.features.clone())
                 .unwrap_or_else(|e| {
@@ -75,23 +147,50 @@ impl IndicateAdapterBuilder {
             self.geiger_client.map_or_else(OnceCell::default, |gc| {
                 OnceCell::with_value(Rc::new(gc))
             });
-        let crates_io_client =
-            self.crates_io_client.map_or_else(OnceCell::default, |c| {
-                OnceCell::with_value(Rc::new(RefCell::new(c)))
-            });
+
+        // Registries set explicitly take precedence over Cargo configuration
+        let mut registries = self.registries;
+        if let Some(dir) = self.manifest_path.as_path().parent() {
+            match registries_from_cargo_config(
+                dir,
+                default_cargo_home().as_deref(),
+            ) {
+                Ok(r) => registries.extend(r),
+                Err(e) => eprintln!(
+                    "could not read registries from Cargo configuration due to error: {e}"
+                ),
+            }
+        }
+        // Only crates in the dependency graph are read from a database dump
+        let crates_io_db_dump = self.crates_io_db_dump.map(|p| {
+            let crate_names =
+                metadata.packages.iter().map(|p| p.name.clone()).collect();
+            (p, crate_names)
+        });
+        let registries = RegistryClients::new(
+            self.crates_io_client,
+            crates_io_db_dump,
+            self.registry_index,
+            registries,
+        );
 
         IndicateAdapter {
             manifest_path: Rc::new(self.manifest_path),
             features: self.features,
             metadata: Rc::new(metadata),
             packages: OnceCell::new(),
-            direct_dependencies: OnceCell::new(),
+            packages_by_name: OnceCell::new(),
+            direct_dependencies: RefCell::default(),
+            dependents: RefCell::default(),
+            depths: OnceCell::new(),
+            dependency_edges: OnceCell::new(),
+            enabled_features: OnceCell::new(),
             gh_client: Rc::new(RefCell::new(
                 self.github_client.unwrap_or_default(),
             )),
             advisory_client,
             geiger_client,
-            crates_io_client,
+            registries: Rc::new(registries),
         }
     }
 
@@ -105,6 +204,18 @@ impl IndicateAdapterBuilder {
         self
     }
 
+    /// Target triple to resolve the dependency graph for when generating
+    /// metadata, like `x86_64-unknown-linux-gnu`
+    ///
+    /// Dependencies only used on other platforms will not be part of the
+    /// dependency graph. Cannot be set explicitly at the same time as metadata,
+    /// and will cause a panic when built.
+    #[must_use]
+    pub fn target_platform(mut self, target_platform: String) -> Self {
+        self.target_platform = Some(target_platform);
+        self
+    }
+
     /// Explicitly set metadata for the adapter
     ///
     /// Note that this metadata will prevent one from being generated using
@@ -151,6 +262,45 @@ impl IndicateAdapterBuilder {
         self.crates_io_client = Some(crates_io_client);
         self
     }
+
+    /// Use a `crates.io` database dump (`db-dump.tar.gz`) instead of the
+    /// `crates.io` API, avoiding its rate limit
+    ///
+    /// The dump is only read if `crates.io` data is used by a query. A client
+    /// set using [`IndicateAdapterBuilder::crates_io_client`] takes precedence.
+    #[must_use]
+    pub fn crates_io_db_dump(mut self, db_dump_path: PathBuf) -> Self {
+        self.crates_io_db_dump = Some(db_dump_path);
+        self
+    }
+
+    /// Use a registry index for the versions of crates and their yank status,
+    /// instead of the `crates.io` API or database dump
+    ///
+    /// This makes fields such as `yanked` and `versionsCount` work offline,
+    /// see [`RegistryIndexClient`]. It is not used by a client set using
+    /// [`IndicateAdapterBuilder::crates_io_client`], use
+    /// [`CratesIoClient::registry_index`] for that instead.
+    #[must_use]
+    pub fn registry_index(
+        mut self,
+        registry_index: RegistryIndexClient,
+    ) -> Self {
+        self.registry_index = Some(registry_index);
+        self
+    }
+
+    /// Configures an alternative registry, so that `registryStats` of its
+    /// packages uses its web API and index instead of `crates.io`
+    ///
+    /// Registries in the `[registries]` table of `.cargo/config.toml` files
+    /// that apply to the manifest path are also read when building, see
+    /// [`registries_from_cargo_config`]. Values set here take precedence.
+    #[must_use]
+    pub fn registry(mut self, registry: RegistryConfig) -> Self {
+        self.registries.push(registry);
+        self
+    }
 }
 
 impl From<IndicateAdapterBuilder> for IndicateAdapter {
@@ -8,15 +8,47 @@
 //! See [the crates.io crawler policy](https://crates.io/policies#crawlers) for
 //! more information.
 
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    time::Duration,
+};
 
-use crates_io_api::{Crate, CrateResponse, SyncClient, Version};
+use chrono::{DateTime, NaiveDate, Utc};
+use crates_io_api::{
+    Crate, CrateDownloads, CrateResponse, SyncClient, User, Version,
+};
 
-use crate::NameVersion;
+use crate::{
+    errors::DbDumpError,
+    registry::{RegistryApiClient, RegistryConfig},
+    registry_index::{IndexVersion, RegistryIndexClient},
+    NameVersion,
+};
 
-/// Wrapper around a [`crates_io_api::SyncClient`], with added caching
+use self::db_dump::DbDump;
+
+pub mod db_dump;
+
+/// The number of days of downloads per date kept by `crates.io`
+pub const DOWNLOAD_HISTORY_DAYS: usize = 90;
+
+/// Where `crates.io` data is retrieved from
+enum Backend {
+    Api(SyncClient),
+    DbDump(DbDump),
+
+    /// The web API of an alternative registry
+    Registry(RegistryApiClient),
+
+    /// No requests are made, so only a registry index can be used
+    Offline,
+}
+
+/// Wrapper around a [`crates_io_api::SyncClient`], a [`DbDump`] or the API of
+/// an alternative registry, with added caching
 pub struct CratesIoClient {
-    client: SyncClient,
+    backend: Backend,
 
     /// Cache between crate name and downloads info
     ///
@@ -24,6 +56,20 @@ pub struct CratesIoClient {
     /// same query, so we store if we were able to find it the first time via
     /// the option.
     cache: HashMap<String, Option<CrateResponse>>,
+
+    /// Cache between crate name and its owners, see [`Self::cache`]
+    owners_cache: HashMap<String, Option<Vec<User>>>,
+
+    /// Cache between crate name and its number of reverse dependencies, see
+    /// [`Self::cache`]
+    reverse_dependencies_cache: HashMap<String, Option<u64>>,
+
+    /// Cache between crate name and its downloads per version and date, see
+    /// [`Self::cache`]
+    downloads_cache: HashMap<String, Option<CrateDownloads>>,
+
+    /// Used before the backend for data available in a registry index
+    registry_index: Option<RegistryIndexClient>,
 }
 
 impl CratesIoClient {
@@ -39,13 +85,87 @@ impl CratesIoClient {
                 panic!("could not create CratesIoClient due to error: {e}");
             });
 
+        Self::with_backend(Backend::Api(client))
+    }
+
+    /// Creates a new client using a `crates.io` database dump instead of the
+    /// API, see [`db_dump`]
+    ///
+    /// The whole dump is read when created, so this is expensive, but no
+    /// requests are made afterwards. Only the crates in `crate_names` are
+    /// kept in memory, if provided.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant if the dump cannot be read or parsed.
+    pub fn from_db_dump(
+        path: &Path,
+        crate_names: Option<&HashSet<String>>,
+    ) -> Result<Self, Box<DbDumpError>> {
+        DbDump::from_path(path, crate_names).map(Self::from)
+    }
+
+    /// Creates a new client that does not make any requests, only answering
+    /// what can be answered by a registry index
+    ///
+    /// See [`CratesIoClient::registry_index`].
+    #[must_use]
+    pub fn offline() -> Self {
+        Self::with_backend(Backend::Offline)
+    }
+
+    /// Creates a new client for an alternative registry, using its web API
+    /// and local index if configured
+    ///
+    /// If the API of the registry cannot be found, only what can be answered
+    /// by a local index is used, see [`CratesIoClient::offline`].
+    #[must_use]
+    pub fn from_registry(config: &RegistryConfig) -> Self {
+        let api_url = config.resolve_api_url().unwrap_or_else(|e| {
+            eprintln!(
+                "could not find the API of registry {} due to error: {e}",
+                config.index_url
+            );
+            None
+        });
+        let client = match api_url {
+            Some(a) => Self::with_backend(Backend::Registry(
+                RegistryApiClient::new(&a, config.token.clone()),
+            )),
+            None => Self::offline(),
+        };
+        match &config.index_path {
+            Some(p) => client.registry_index(RegistryIndexClient::new(p)),
+            None => client,
+        }
+    }
+
+    fn with_backend(backend: Backend) -> Self {
         Self {
-            client,
+            backend,
             cache: HashMap::new(),
+            owners_cache: HashMap::new(),
+            reverse_dependencies_cache: HashMap::new(),
+            downloads_cache: HashMap::new(),
+            registry_index: None,
         }
     }
 
-    /// Retrieves information about a crate from the `crates.io` API
+    /// Use a registry index for versions and their yank status, instead of
+    /// the `crates.io` API or database dump
+    ///
+    /// Crates that are not found in the index are still retrieved using the
+    /// API or database dump.
+    #[must_use]
+    pub fn registry_index(
+        mut self,
+        registry_index: RegistryIndexClient,
+    ) -> Self {
+        self.registry_index = Some(registry_index);
+        self
+    }
+
+    /// Retrieves information about a crate from `crates.io`
     ///
     /// Will return `None` if the request fails, and will cache this crate as
     /// such.
@@ -54,16 +174,186 @@ impl CratesIoClient {
         crate_name: &str,
     ) -> Option<&mut CrateResponse> {
         self.cache.entry(crate_name.to_string()).or_insert_with(|| {
-           match self.client.get_crate(crate_name)  {
-                Ok(cr) => Some(cr),
-                Err(e) => {
-                    eprintln!("failed to retrieve crates.io information about {crate_name} due to error: {e}");
-                    None
+            match &mut self.backend {
+                Backend::Api(client) => match client.get_crate(crate_name) {
+                    Ok(cr) => Some(cr),
+                    Err(e) => {
+                        eprintln!("failed to retrieve crates.io information about {crate_name} due to error: {e}");
+                        None
+                    }
+                },
+                Backend::DbDump(dump) => {
+                    let cr = dump.take_crate(crate_name);
+                    if cr.is_none() {
+                        eprintln!("could not find {crate_name} in crates.io database dump");
+                    }
+                    cr
                 }
+                Backend::Registry(client) => match client.get_crate(crate_name) {
+                    Ok(cr) => Some(cr),
+                    Err(e) => {
+                        eprintln!("failed to retrieve registry information about {crate_name} due to error: {e}");
+                        None
+                    }
+                },
+
+                Backend::Offline => None,
             }
         }).as_mut()
     }
 
+    /// Retrieves the users and teams that may publish new versions of a crate
+    /// from the `crates.io` API
+    ///
+    /// Will return `None` if the request fails, and will cache this crate as
+    /// such.
+    pub fn owners(&mut self, crate_name: &str) -> Option<&Vec<User>> {
+        self.owners_cache.entry(crate_name.to_string()).or_insert_with(|| {
+            match &mut self.backend {
+                Backend::Api(client) => match client.crate_owners(crate_name) {
+                    Ok(owners) => Some(owners),
+                    Err(e) => {
+                        eprintln!("failed to retrieve crates.io owners of {crate_name} due to error: {e}");
+                        None
+                    }
+                },
+                Backend::DbDump(dump) => dump.take_owners(crate_name),
+                Backend::Registry(client) => match client.crate_owners(crate_name) {
+                    Ok(owners) => Some(owners),
+                    Err(e) => {
+                        eprintln!("failed to retrieve registry owners of {crate_name} due to error: {e}");
+                        None
+                    }
+                },
+                Backend::Offline => None,
+            }
+        }).as_ref()
+    }
+
+    /// Retrieves the number of crates that depend on a crate in their newest
+    /// version that is not yanked
+    ///
+    /// Will return `None` if the request fails, and will cache this crate as
+    /// such.
+    pub fn reverse_dependencies_count(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<u64> {
+        *self.reverse_dependencies_cache.entry(crate_name.to_string()).or_insert_with(|| {
+            match &self.backend {
+                Backend::Api(client) => match client.crate_reverse_dependency_count(crate_name) {
+                    Ok(count) => Some(count),
+                    Err(e) => {
+                        eprintln!("failed to retrieve crates.io reverse dependencies of {crate_name} due to error: {e}");
+                        None
+                    }
+                },
+                Backend::DbDump(dump) => dump.reverse_dependencies_count(crate_name),
+                Backend::Registry(client) => match client.reverse_dependencies_count(crate_name) {
+                    Ok(count) => Some(count),
+                    Err(e) => {
+                        eprintln!("failed to retrieve registry reverse dependencies of {crate_name} due to error: {e}");
+                        None
+                    }
+                },
+                Backend::Offline => None,
+            }
+        })
+    }
+
+    /// Retrieves the downloads per date of the last
+    /// [`DOWNLOAD_HISTORY_DAYS`] days of a crate
+    ///
+    /// Will return `None` if the request fails, and will cache this crate as
+    /// such.
+    pub fn downloads(&mut self, crate_name: &str) -> Option<&CrateDownloads> {
+        self.downloads_cache.entry(crate_name.to_string()).or_insert_with(|| {
+            match &mut self.backend {
+                Backend::Api(client) => match client.crate_downloads(crate_name) {
+                    Ok(downloads) => Some(downloads),
+                    Err(e) => {
+                        eprintln!("failed to retrieve crates.io downloads of {crate_name} due to error: {e}");
+                        None
+                    }
+                },
+                Backend::DbDump(dump) => dump.take_downloads(crate_name),
+                Backend::Registry(client) => match client.crate_downloads(crate_name) {
+                    Ok(downloads) => Some(downloads),
+                    Err(e) => {
+                        eprintln!("failed to retrieve registry downloads of {crate_name} due to error: {e}");
+                        None
+                    }
+                },
+                Backend::Offline => None,
+            }
+        }).as_ref()
+    }
+
+    /// Retrieves the downloads per day of the last `days` days of a crate,
+    /// oldest first, see [`daily_downloads`]
+    ///
+    /// If `locked_version_only` is set, only downloads of this version are
+    /// counted. The API only includes downloads of a few recent versions
+    /// separately, so this may be all zeroes for other versions.
+    pub fn download_history(
+        &mut self,
+        name_version: &NameVersion,
+        days: usize,
+        locked_version_only: bool,
+    ) -> Option<Vec<DownloadPoint>> {
+        let version_id = if locked_version_only {
+            Some(self.version(name_version)?.id)
+        } else {
+            None
+        };
+        let until = self.now().date_naive();
+        self.downloads(&name_version.name)
+            .map(|d| daily_downloads(d, version_id, days, until))
+    }
+
+    /// Fits a line to the downloads per day of a crate over the last
+    /// [`DOWNLOAD_HISTORY_DAYS`] days, returning its slope
+    ///
+    /// The slope is in downloads per day per day, so it is positive for
+    /// crates that are downloaded more and more.
+    pub fn download_trend_slope(&mut self, crate_name: &str) -> Option<f64> {
+        let until = self.now().date_naive();
+        self.downloads(crate_name).and_then(|d| {
+            trend_slope(&daily_downloads(d, None, DOWNLOAD_HISTORY_DAYS, until))
+        })
+    }
+
+    /// The time the data is from, which is when the database dump was
+    /// created if one is used, and now otherwise
+    fn now(&self) -> DateTime<Utc> {
+        match &self.backend {
+            Backend::DbDump(dump) => dump.created_at(),
+            _ => None,
+        }
+        .unwrap_or_else(Utc::now)
+    }
+
+    /// Retrieves the user that published this version to `crates.io`, which
+    /// is not known for versions published before 2019
+    pub fn published_by(&mut self, name_version: &NameVersion) -> Option<User> {
+        self.versions(&name_version.name).and_then(|versions| {
+            parsed_versions(versions)
+                .find(|(parsed, _)| *parsed == name_version.version)
+                .and_then(|(_, v)| v.published_by.clone())
+        })
+    }
+
+    /// Versions of a crate in the registry index, if one is used and it
+    /// contains the crate
+    fn index_versions(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<&Vec<IndexVersion>> {
+        self.registry_index
+            .as_mut()
+            .and_then(|i| i.versions(crate_name))
+    }
+
     /// Retrieve data about a crate from the `crates.io` API
     pub fn crate_data(&mut self, crate_name: &str) -> Option<&Crate> {
         self.crate_response(crate_name).map(|cr| &cr.crate_data)
@@ -74,8 +364,21 @@ impl CratesIoClient {
         self.crate_response(crate_name).map(|cr| &cr.versions)
     }
 
+    /// Retrieves data about a specific version of a crate from the `crates.io`
+    /// API
+    pub fn version(&mut self, name_version: &NameVersion) -> Option<&Version> {
+        self.versions(&name_version.name).and_then(|versions| {
+            parsed_versions(versions)
+                .find(|(parsed, _)| *parsed == name_version.version)
+                .map(|(_, v)| v)
+        })
+    }
+
     /// Returns the number of versions of a crate from the `crates.io` API
     pub fn versions_count(&mut self, crate_name: &str) -> Option<usize> {
+        if let Some(versions) = self.index_versions(crate_name) {
+            return Some(versions.len());
+        }
         self.versions(crate_name).map(Vec::len)
     }
 
@@ -113,6 +416,13 @@ impl CratesIoClient {
 
     /// Returns if this version is yanked from `crates.io`
     pub fn yanked(&mut self, name_version: &NameVersion) -> Option<bool> {
+        if let Some(yanked) = self
+            .registry_index
+            .as_mut()
+            .and_then(|i| i.yanked(name_version))
+        {
+            return Some(yanked);
+        }
         self.versions(&name_version.name).and_then(|versions| {
             versions.iter().find_map(|v| {
                 match rustsec::Version::parse(&v.num) {
@@ -137,6 +447,13 @@ impl CratesIoClient {
     /// If only the count of yanked versions is desired, use
     /// [`yanked_versions_count`](Self::yanked_versions_count) instead.
     pub fn yanked_versions(&mut self, crate_name: &str) -> Option<Vec<String>> {
+        if let Some(yanked) = self
+            .registry_index
+            .as_mut()
+            .and_then(|i| i.yanked_versions(crate_name))
+        {
+            return Some(yanked);
+        }
         self.versions(crate_name).map(|versions| {
             versions
                 .iter()
@@ -156,11 +473,110 @@ impl CratesIoClient {
     /// Counts the number of versions marked as _yanked_ on `crates.io` for this
     /// crate
     pub fn yanked_versions_count(&mut self, crate_name: &str) -> Option<usize> {
+        if let Some(versions) = self.index_versions(crate_name) {
+            return Some(versions.iter().filter(|v| v.yanked).count());
+        }
+
         // Do not rely on Self::yanked_version, as it is more expensive
         self.versions(crate_name)
             .map(|versions| versions.iter().filter(|v| v.yanked).count())
     }
 
+    /// Retrieves the highest version of a crate that is not yanked,
+    /// including prereleases
+    pub fn latest_version(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<rustsec::Version> {
+        self.versions(crate_name)
+            .and_then(|versions| latest_version(versions, false))
+    }
+
+    /// Retrieves the highest version of a crate that is neither yanked nor a
+    /// prerelease
+    pub fn latest_stable_version(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<rustsec::Version> {
+        self.versions(crate_name)
+            .and_then(|versions| latest_version(versions, true))
+    }
+
+    /// Checks if there is a stable version of a crate that is newer than this
+    /// version
+    pub fn is_outdated(&mut self, name_version: &NameVersion) -> Option<bool> {
+        self.latest_stable_version(&name_version.name)
+            .map(|latest| latest > name_version.version)
+    }
+
+    /// Counts the semver incompatible release lines newer than this version,
+    /// ignoring yanked versions and prereleases
+    ///
+    /// See [`major_versions_behind`] for how release lines are defined.
+    pub fn major_versions_behind(
+        &mut self,
+        name_version: &NameVersion,
+    ) -> Option<usize> {
+        self.versions(&name_version.name).map(|versions| {
+            major_versions_behind(versions, &name_version.version)
+        })
+    }
+
+    /// Counts the days since this version was published to `crates.io`, up
+    /// until the database dump was created if one is used
+    pub fn days_since_version_released(
+        &mut self,
+        name_version: &NameVersion,
+    ) -> Option<i64> {
+        let now = self.now();
+        self.versions(&name_version.name).and_then(|versions| {
+            parsed_versions(versions)
+                .find(|(parsed, _)| *parsed == name_version.version)
+                .map(|(_, v)| days_since(v.created_at, now))
+        })
+    }
+
+    /// Counts the days since a version of this crate that is not yanked was
+    /// last published to `crates.io`, up until the database dump was created
+    /// if one is used
+    pub fn days_since_latest_release(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<i64> {
+        let now = self.now();
+        self.versions(crate_name).and_then(|versions| {
+            versions
+                .iter()
+                .filter(|v| !v.yanked)
+                .map(|v| v.created_at)
+                .max()
+                .map(|created_at| days_since(created_at, now))
+        })
+    }
+
+    /// Lists all versions of a crate in the order they were published,
+    /// including who published them
+    pub fn publisher_history(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<Vec<PublisherHistoryEntry>> {
+        self.versions(crate_name)
+            .map(|versions| publisher_history(versions))
+    }
+
+    /// Checks if the newest release of a crate was published by someone that
+    /// did not publish this version or any earlier version
+    ///
+    /// Returns `None` if the publishers are not known.
+    pub fn publisher_changed_since(
+        &mut self,
+        name_version: &NameVersion,
+    ) -> Option<bool> {
+        self.versions(&name_version.name).and_then(|versions| {
+            publisher_changed_since(versions, &name_version.version)
+        })
+    }
+
     /// Calculates the ratio of yanked versions to all crate versions
     pub fn yanked_ratio(&mut self, crate_name: &str) -> Option<f64> {
         self.yanked_versions_count(crate_name).and_then(|y| {
@@ -169,6 +585,219 @@ impl CratesIoClient {
     }
 }
 
+/// A version of a crate in its publishing history, see
+/// [`CratesIoClient::publisher_history`]
+#[derive(Debug, Clone)]
+pub struct PublisherHistoryEntry {
+    pub version: Version,
+
+    /// If this version was published by someone that did not publish any
+    /// earlier version, `None` if that is not known
+    pub is_new_publisher: Option<bool>,
+}
+
+/// Downloads of a crate on a date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadPoint {
+    pub date: NaiveDate,
+    pub downloads: u64,
+}
+
+/// Sums the downloads of a crate per date, only counting the version with
+/// `version_id` if set
+///
+/// The last `days` days up until and including `until` are included, oldest
+/// first. Dates without downloads are included with zero downloads.
+fn daily_downloads(
+    downloads: &CrateDownloads,
+    version_id: Option<u64>,
+    days: usize,
+    until: NaiveDate,
+) -> Vec<DownloadPoint> {
+    let versions = downloads.version_downloads.iter().filter_map(|d| {
+        (version_id.is_none() || version_id == Some(d.version))
+            .then_some((d.date, d.downloads))
+    });
+
+    // Downloads of versions not listed separately
+    let extra = downloads
+        .meta
+        .extra_downloads
+        .iter()
+        .filter(|_| version_id.is_none())
+        .map(|d| (d.date, d.downloads));
+
+    let mut by_date = (0..days as i64)
+        .map(|i| (until - chrono::Duration::days(i), 0))
+        .collect::<BTreeMap<_, u64>>();
+    for (date, n) in versions.chain(extra) {
+        if let Some(total) = by_date.get_mut(&date) {
+            *total += n;
+        }
+    }
+
+    by_date
+        .into_iter()
+        .map(|(date, downloads)| DownloadPoint { date, downloads })
+        .collect()
+}
+
+/// The slope of a least squares line fitted to downloads per day, `None` if
+/// there are fewer than two days
+fn trend_slope(points: &[DownloadPoint]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = points.iter().map(|p| p.downloads as f64).sum::<f64>() / n;
+    let (covariance, variance) = points.iter().enumerate().fold(
+        (0.0, 0.0),
+        |(covariance, variance), (i, p)| {
+            let dx = i as f64 - mean_x;
+            (
+                covariance + dx * (p.downloads as f64 - mean_y),
+                variance + dx * dx,
+            )
+        },
+    );
+    Some(covariance / variance)
+}
+
+/// Pairs all versions in the order they were published with whether their
+/// publisher is new
+///
+/// Publishers are only known for versions published since 2019, so versions
+/// without any earlier known publisher are not considered new.
+fn publisher_history(versions: &[Version]) -> Vec<PublisherHistoryEntry> {
+    let mut by_date = versions.iter().collect::<Vec<_>>();
+    by_date.sort_by_key(|v| v.created_at);
+
+    let mut seen = HashSet::new();
+    by_date
+        .into_iter()
+        .map(|v| {
+            let is_new_publisher = v.published_by.as_ref().and_then(|u| {
+                let is_new = (!seen.is_empty()).then(|| !seen.contains(&u.id));
+                seen.insert(u.id);
+                is_new
+            });
+            PublisherHistoryEntry {
+                version: v.clone(),
+                is_new_publisher,
+            }
+        })
+        .collect()
+}
+
+/// Checks if the newest release that is not yanked was published by someone
+/// that did not publish this version or any version before it
+fn publisher_changed_since(
+    versions: &[Version],
+    version: &rustsec::Version,
+) -> Option<bool> {
+    let released_at = parsed_versions(versions)
+        .find(|(parsed, _)| parsed == version)
+        .map(|(_, v)| v.created_at)?;
+    let newest = versions
+        .iter()
+        .filter(|v| !v.yanked)
+        .max_by_key(|v| v.created_at)?;
+    if newest.created_at <= released_at {
+        return Some(false);
+    }
+
+    let known = versions
+        .iter()
+        .filter(|v| v.created_at <= released_at)
+        .filter_map(|v| v.published_by.as_ref().map(|u| u.id))
+        .collect::<HashSet<_>>();
+    if known.is_empty() {
+        return None;
+    }
+    newest.published_by.as_ref().map(|u| !known.contains(&u.id))
+}
+
+/// Versions of a crate with a version number that can be parsed, paired with
+/// the parsed version
+fn parsed_versions(
+    versions: &[Version],
+) -> impl Iterator<Item = (rustsec::Version, &Version)> {
+    versions.iter().filter_map(|v| {
+        rustsec::Version::parse(&v.num)
+            .ok()
+            .map(|parsed| (parsed, v))
+    })
+}
+
+/// The highest version that is not yanked, and optionally not a prerelease
+fn latest_version(
+    versions: &[Version],
+    stable_only: bool,
+) -> Option<rustsec::Version> {
+    parsed_versions(versions)
+        .filter(|(parsed, v)| {
+            !v.yanked && (!stable_only || parsed.pre.is_empty())
+        })
+        .map(|(parsed, _)| parsed)
+        .max()
+}
+
+/// The part of a version that is semver incompatible when changed, i.e. the
+/// major version, or the minor version for `0.x` versions, or the patch version
+/// for `0.0.x` versions
+fn release_line(version: &rustsec::Version) -> (u64, u64, u64) {
+    match (version.major, version.minor) {
+        (0, 0) => (0, 0, version.patch),
+        (0, minor) => (0, minor, 0),
+        (major, _) => (major, 0, 0),
+    }
+}
+
+/// Counts the release lines with versions that are neither yanked nor
+/// prereleases, newer than the release line of this version
+///
+/// For example, `1.0.0` is one release line behind `2.1.0`, and `0.2.0` is two
+/// release lines behind `0.4.0` if there is a `0.3.x` version.
+fn major_versions_behind(
+    versions: &[Version],
+    version: &rustsec::Version,
+) -> usize {
+    let current_line = release_line(version);
+    let mut newer_lines = parsed_versions(versions)
+        .filter(|(parsed, v)| !v.yanked && parsed.pre.is_empty())
+        .map(|(parsed, _)| release_line(&parsed))
+        .filter(|line| *line > current_line)
+        .collect::<Vec<_>>();
+    newer_lines.sort_unstable();
+    newer_lines.dedup();
+    newer_lines.len()
+}
+
+fn days_since(time: DateTime<Utc>, now: DateTime<Utc>) -> i64 {
+    (now - time).num_days()
+}
+
+/// Whether a crates.io user is a `"user"` or a `"team"`
+///
+/// The kind is only included when listing owners, so the login is used for
+/// publishers, where teams are named like `github:org:team`.
+pub(crate) fn user_kind(user: &User) -> &'static str {
+    match user.kind.as_deref() {
+        Some("team") => "team",
+        Some(_) => "user",
+        None if user.login.contains(':') => "team",
+        None => "user",
+    }
+}
+
+impl From<DbDump> for CratesIoClient {
+    fn from(value: DbDump) -> Self {
+        Self::with_backend(Backend::DbDump(value))
+    }
+}
+
 impl Default for CratesIoClient {
     fn default() -> Self {
         let user_agent = std::env::var("USER_AGENT")
@@ -176,3 +805,165 @@ impl Default for CratesIoClient {
         Self::new(&user_agent, Duration::from_secs(1))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crates_io_api::{CrateDownloads, User, Version};
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::{
+        daily_downloads, latest_version, major_versions_behind,
+        publisher_changed_since, publisher_history, trend_slope, user_kind,
+        DownloadPoint,
+    };
+
+    /// Creates versions as returned by the crates.io API, with the versions
+    /// ending with `!` being yanked
+    fn versions(nums: &[&str]) -> Vec<Version> {
+        nums.iter()
+            .enumerate()
+            .map(|(i, num)| {
+                serde_json::from_value(json!({
+                    "crate": "fake",
+                    "created_at": format!("2023-01-{:02}T00:00:00Z", i + 1),
+                    "updated_at": "2023-01-01T00:00:00Z",
+                    "dl_path": "",
+                    "downloads": 0,
+                    "features": {},
+                    "id": i,
+                    "num": num.trim_end_matches('!'),
+                    "yanked": num.ends_with('!'),
+                    "license": null,
+                    "readme_path": null,
+                    "links": {
+                        "dependencies": "",
+                        "version_downloads": "",
+                    },
+                    "crate_size": null,
+                    "published_by": null,
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    /// Creates versions published by users with the given ids, where `0`
+    /// means the publisher is not known
+    fn published_versions(nums: &[&str], publishers: &[u64]) -> Vec<Version> {
+        let mut versions = versions(nums);
+        for (v, &id) in versions.iter_mut().zip(publishers) {
+            v.published_by = (id != 0).then(|| user(&format!("user{id}"), id));
+        }
+        versions
+    }
+
+    fn user(login: &str, id: u64) -> User {
+        serde_json::from_value(json!({
+            "avatar": null,
+            "email": null,
+            "id": id,
+            "kind": null,
+            "login": login,
+            "name": null,
+            "url": format!("https://github.com/{login}"),
+        }))
+        .unwrap()
+    }
+
+    #[test_case(&["1.0.0", "1.1.0", "2.0.0-rc.1"], false => Some(String::from("2.0.0-rc.1")) ; "prerelease")]
+    #[test_case(&["1.0.0", "1.1.0", "2.0.0-rc.1"], true => Some(String::from("1.1.0")) ; "stable")]
+    #[test_case(&["1.0.0", "1.1.0!"], true => Some(String::from("1.0.0")) ; "yanked")]
+    #[test_case(&["1.0.0!"], false => None ; "all yanked")]
+    fn latest(nums: &[&str], stable_only: bool) -> Option<String> {
+        latest_version(&versions(nums), stable_only).map(|v| v.to_string())
+    }
+
+    #[test_case(&["1.0.0", "1.1.0", "2.0.0", "3.0.0", "3.1.0"], "1.0.0" => 2 ; "major versions")]
+    #[test_case(&["1.0.0", "1.1.0"], "1.0.0" => 0 ; "compatible version")]
+    #[test_case(&["0.1.0", "0.2.0", "0.3.0", "0.3.1"], "0.1.5" => 2 ; "minor versions before 1.0.0")]
+    #[test_case(&["0.0.1", "0.0.2", "0.0.3"], "0.0.1" => 2 ; "patch versions before 0.1.0")]
+    #[test_case(&["1.0.0", "2.0.0!", "3.0.0-rc.1"], "1.0.0" => 0 ; "yanked and prereleases")]
+    fn versions_behind(nums: &[&str], version: &str) -> usize {
+        let version = rustsec::Version::parse(version).unwrap();
+        major_versions_behind(&versions(nums), &version)
+    }
+
+    #[test_case("dtolnay", Some("user") => "user" ; "user")]
+    #[test_case("github:rust-lang:libs", Some("team") => "team" ; "team")]
+    #[test_case("dtolnay", None => "user" ; "publisher")]
+    #[test_case("github:rust-lang:libs", None => "team" ; "team without kind")]
+    fn kind(login: &str, kind: Option<&str>) -> &'static str {
+        let mut user = user(login, 1);
+        user.kind = kind.map(String::from);
+        user_kind(&user)
+    }
+
+    #[test_case(&[1, 1, 2, 1] => vec![None, Some(false), Some(true), Some(false)] ; "new publisher")]
+    #[test_case(&[0, 0, 1, 1] => vec![None, None, None, Some(false)] ; "unknown publishers")]
+    fn new_publishers(publishers: &[u64]) -> Vec<Option<bool>> {
+        let nums = ["1.0.0", "1.1.0", "1.2.0", "1.3.0"];
+        publisher_history(&published_versions(&nums, publishers))
+            .into_iter()
+            .map(|e| e.is_new_publisher)
+            .collect()
+    }
+
+    #[test_case(&["1.0.0", "1.1.0", "1.2.0"], &[1, 1, 2], "1.1.0" => Some(true) ; "newest from someone new")]
+    #[test_case(&["1.0.0", "1.1.0", "1.2.0"], &[2, 1, 2], "1.1.0" => Some(false) ; "newest from earlier publisher")]
+    #[test_case(&["1.0.0", "1.1.0", "1.2.0!"], &[1, 1, 2], "1.1.0" => Some(false) ; "newest yanked")]
+    #[test_case(&["1.0.0", "1.1.0"], &[1, 2], "1.1.0" => Some(false) ; "is newest")]
+    #[test_case(&["1.0.0", "1.1.0"], &[0, 2], "1.0.0" => None ; "unknown publishers")]
+    #[test_case(&["1.0.0"], &[1], "2.0.0" => None ; "unknown version")]
+    fn publisher_changed(
+        nums: &[&str],
+        publishers: &[u64],
+        version: &str,
+    ) -> Option<bool> {
+        let version = rustsec::Version::parse(version).unwrap();
+        publisher_changed_since(&published_versions(nums, publishers), &version)
+    }
+
+    fn downloads() -> CrateDownloads {
+        serde_json::from_value(json!({
+            "version_downloads": [
+                { "date": "2023-05-29", "downloads": 10, "version": 1 },
+                { "date": "2023-05-31", "downloads": 20, "version": 1 },
+                { "date": "2023-05-31", "downloads": 5, "version": 2 },
+            ],
+            "meta": {
+                "extra_downloads": [
+                    { "date": "2023-05-30", "downloads": 1 },
+                ],
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test_case(None, 3, "2023-05-31" => vec![10, 1, 25] ; "all versions")]
+    #[test_case(Some(1), 3, "2023-05-31" => vec![10, 0, 20] ; "one version")]
+    #[test_case(None, 5, "2023-05-31" => vec![0, 0, 10, 1, 25] ; "days without downloads")]
+    #[test_case(None, 1, "2023-05-31" => vec![25] ; "latest day")]
+    #[test_case(None, 4, "2023-06-02" => vec![1, 25, 0, 0] ; "no recent downloads")]
+    #[test_case(None, 2, "2023-05-30" => vec![10, 1] ; "later downloads ignored")]
+    fn daily(version_id: Option<u64>, days: usize, until: &str) -> Vec<u64> {
+        let until = until.parse().unwrap();
+        daily_downloads(&downloads(), version_id, days, until)
+            .into_iter()
+            .map(|p| p.downloads)
+            .collect()
+    }
+
+    #[test_case(&[1, 2, 3] => Some(1.0) ; "growing")]
+    #[test_case(&[6, 4, 2, 0] => Some(-2.0) ; "declining")]
+    #[test_case(&[5, 5] => Some(0.0) ; "steady")]
+    #[test_case(&[5] => None ; "one day")]
+    fn slope(downloads: &[u64]) -> Option<f64> {
+        let date = chrono::NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let points = downloads
+            .iter()
+            .map(|&downloads| DownloadPoint { date, downloads })
+            .collect::<Vec<_>>();
+        trend_slope(&points)
+    }
+}
@@ -0,0 +1,747 @@
+//! Reading `crates.io` data from the database dump, available at
+//! <https://static.crates.io/db-dump.tar.gz>
+//!
+//! The dump is updated daily, and avoids the rate limit of the `crates.io` API
+//! when many crates are to be queried. Only the tables needed to answer the
+//! same questions as the API are read, so categories and keywords are not
+//! included.
+//!
+//! See [the crates.io data access policy](https://crates.io/data-access) for
+//! more information.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    fs::File,
+    io::Read,
+    path::Path,
+};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use crates_io_api::{
+    Crate, CrateDownloads, CrateDownloadsMeta, CrateLinks, CrateResponse, User,
+    Version, VersionDownloads, VersionLinks,
+};
+use flate2::read::GzDecoder;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+use crate::errors::DbDumpError;
+
+use super::latest_version;
+
+/// Format of timestamps in the database dump, which do not include a timezone
+/// but are in UTC
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// Format of the name of the directory all tables are in, which is when the
+/// dump was created in UTC
+const DIRECTORY_FORMAT: &str = "%Y-%m-%d-%H%M%S";
+
+/// Data about all crates in a `crates.io` database dump, in the same format as
+/// the `crates.io` API
+#[derive(Debug, Default)]
+pub struct DbDump {
+    crates: HashMap<String, CrateResponse>,
+    owners: HashMap<String, Vec<User>>,
+
+    /// Downloads per version and date, `None` if the dump does not include
+    /// them
+    downloads: Option<HashMap<String, CrateDownloads>>,
+
+    /// Number of crates depending on each crate, `None` if the dump does not
+    /// include dependencies
+    reverse_dependencies: Option<HashMap<String, u64>>,
+
+    /// When the dump was created, `None` if it could not be read from the
+    /// name of its directory
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl DbDump {
+    /// Reads a `db-dump.tar.gz` file
+    ///
+    /// The full dump is several gigabytes when decompressed, so only the
+    /// crates in `crate_names` are kept, if provided.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant if the file cannot be read, or if any of the
+    /// tables in it cannot be parsed.
+    pub fn from_path(
+        path: &Path,
+        crate_names: Option<&HashSet<String>>,
+    ) -> Result<Self, Box<DbDumpError>> {
+        let file = File::open(path).map_err(|e| {
+            DbDumpError::CouldNotRead(
+                path.to_string_lossy().to_string(),
+                e.to_string(),
+            )
+        })?;
+        Self::read(file, &path.to_string_lossy(), crate_names)
+    }
+
+    /// Reads a gzipped tarball in the format of `db-dump.tar.gz`, keeping
+    /// only the crates in `crate_names` if provided
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant if the tarball cannot be read, or if any of
+    /// the tables in it cannot be parsed.
+    pub fn from_reader(
+        reader: impl Read,
+        crate_names: Option<&HashSet<String>>,
+    ) -> Result<Self, Box<DbDumpError>> {
+        Self::read(reader, "db-dump.tar.gz", crate_names)
+    }
+
+    fn read(
+        reader: impl Read,
+        source: &str,
+        crate_names: Option<&HashSet<String>>,
+    ) -> Result<Self, Box<DbDumpError>> {
+        let could_not_read = |e: std::io::Error| {
+            Box::new(DbDumpError::CouldNotRead(
+                source.to_string(),
+                e.to_string(),
+            ))
+        };
+
+        let mut tables = Tables {
+            crate_names,
+            ..Tables::default()
+        };
+        let mut archive = tar::Archive::new(GzDecoder::new(reader));
+        for entry in archive.entries().map_err(could_not_read)? {
+            let entry = entry.map_err(could_not_read)?;
+            let path = entry.path().map_err(could_not_read)?.into_owned();
+            tables.read_table(&path, entry)?;
+        }
+
+        Ok(tables.into_db_dump())
+    }
+
+    /// Removes the data about a crate from the dump, to be cached elsewhere
+    pub(crate) fn take_crate(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<CrateResponse> {
+        self.crates.remove(crate_name)
+    }
+
+    /// Removes the owners of a crate from the dump, to be cached elsewhere
+    pub(crate) fn take_owners(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<Vec<User>> {
+        self.owners.remove(crate_name)
+    }
+
+    /// Removes the downloads per version and date of a crate from the dump,
+    /// to be cached elsewhere
+    pub(crate) fn take_downloads(
+        &mut self,
+        crate_name: &str,
+    ) -> Option<CrateDownloads> {
+        self.downloads.as_mut().and_then(|d| d.remove(crate_name))
+    }
+
+    /// The number of crates whose newest version that is not yanked depends
+    /// on this crate, like the `crates.io` API counts them
+    pub(crate) fn reverse_dependencies_count(
+        &self,
+        crate_name: &str,
+    ) -> Option<u64> {
+        self.reverse_dependencies
+            .as_ref()
+            .and_then(|r| r.get(crate_name).copied())
+    }
+
+    /// When the dump was created, i.e. the last time it may include data
+    /// for
+    pub(crate) fn created_at(&self) -> Option<DateTime<Utc>> {
+        self.created_at
+    }
+}
+
+/// Rows of the tables in the dump, in whichever order they appear in the
+/// tarball
+///
+/// Rows of crates not in `crate_names` are dropped as soon as it is known
+/// which crate they belong to, which depends on the order of the tables. The
+/// remaining ones are dropped when joining the tables.
+#[derive(Default)]
+struct Tables<'a> {
+    /// The crates to keep, or `None` to keep all
+    crate_names: Option<&'a HashSet<String>>,
+
+    /// IDs of the kept crates, once `crates.csv` has been read
+    crate_ids: Option<HashSet<u64>>,
+
+    /// IDs of the versions of the kept crates, once `versions.csv` has been
+    /// read
+    version_ids: Option<HashSet<u64>>,
+
+    /// The newest version that is not yanked of every crate, including those
+    /// not kept, to count reverse dependencies
+    newest_versions: HashMap<u64, (DateTime<Utc>, u64)>,
+
+    crates: Vec<CrateRow>,
+    crate_downloads: HashMap<u64, u64>,
+    versions: Vec<VersionRow>,
+    version_downloads: Option<HashMap<u64, Vec<VersionDownloads>>>,
+    users: HashMap<u64, User>,
+    teams: HashMap<u64, User>,
+    crate_owners: Vec<CrateOwnerRow>,
+    dependencies: Option<Vec<DependencyRow>>,
+
+    /// Parsed from the directory all tables are in, named
+    /// `<date>-<time>`
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl Tables<'_> {
+    /// If rows of a crate are to be kept, as far as is known yet
+    fn keep_crate(&self, crate_id: u64) -> bool {
+        self.crate_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&crate_id))
+    }
+
+    /// If rows of a version are to be kept, as far as is known yet
+    fn keep_version(&self, version_id: u64) -> bool {
+        self.version_ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&version_id))
+    }
+
+    /// Reads a table if it is one of the ones used, identified by its path
+    /// `<date>/data/<table>.csv`
+    fn read_table(
+        &mut self,
+        path: &Path,
+        reader: impl Read,
+    ) -> Result<(), Box<DbDumpError>> {
+        if self.created_at.is_none() {
+            self.created_at = path
+                .components()
+                .next()
+                .and_then(|c| c.as_os_str().to_str())
+                .and_then(|d| {
+                    NaiveDateTime::parse_from_str(d, DIRECTORY_FORMAT).ok()
+                })
+                .map(|dt| Utc.from_utc_datetime(&dt));
+        }
+
+        if path.parent().and_then(Path::file_name) != Some(OsStr::new("data")) {
+            return Ok(());
+        }
+
+        let Some(table) = path.file_name().and_then(|n| n.to_str()) else {
+            return Ok(());
+        };
+        match table {
+            "crates.csv" => {
+                let crate_names = self.crate_names;
+                self.crates = read_rows(path, reader, |r: &CrateRow| {
+                    crate_names.is_none_or(|n| n.contains(&r.name))
+                })?;
+                if crate_names.is_some() {
+                    self.crate_ids =
+                        Some(self.crates.iter().map(|c| c.id).collect());
+                }
+            }
+            "crate_downloads.csv" => {
+                self.crate_downloads =
+                    read_rows(path, reader, |r: &CrateDownloadsRow| {
+                        self.keep_crate(r.crate_id)
+                    })?
+                    .into_iter()
+                    .map(|r| (r.crate_id, r.downloads))
+                    .collect();
+            }
+            "versions.csv" => {
+                let mut newest_versions = HashMap::new();
+                self.versions = read_rows(path, reader, |r: &VersionRow| {
+                    if !r.yanked {
+                        let newest = newest_versions
+                            .entry(r.crate_id)
+                            .or_insert((r.created_at, r.id));
+                        if r.created_at > newest.0 {
+                            *newest = (r.created_at, r.id);
+                        }
+                    }
+                    self.keep_crate(r.crate_id)
+                })?;
+                self.newest_versions = newest_versions;
+                if self.crate_ids.is_some() {
+                    self.version_ids =
+                        Some(self.versions.iter().map(|v| v.id).collect());
+                }
+            }
+            "version_downloads.csv" => {
+                let mut downloads: HashMap<_, Vec<_>> = HashMap::new();
+                let rows =
+                    read_rows(path, reader, |r: &VersionDownloadsRow| {
+                        self.keep_version(r.version_id)
+                    })?;
+                for r in rows {
+                    downloads.entry(r.version_id).or_default().push(
+                        VersionDownloads {
+                            date: r.date,
+                            downloads: r.downloads,
+                            version: r.version_id,
+                        },
+                    );
+                }
+                self.version_downloads = Some(downloads);
+            }
+            "users.csv" => {
+                self.users = read_rows(path, reader, |_: &UserRow| true)?
+                    .into_iter()
+                    .map(|r| (r.id, r.into()))
+                    .collect();
+            }
+            "teams.csv" => {
+                self.teams = read_rows(path, reader, |_: &TeamRow| true)?
+                    .into_iter()
+                    .map(|r| (r.id, r.into()))
+                    .collect();
+            }
+            "crate_owners.csv" => {
+                self.crate_owners =
+                    read_rows(path, reader, |r: &CrateOwnerRow| {
+                        self.keep_crate(r.crate_id)
+                    })?;
+            }
+            "dependencies.csv" => {
+                // Dependents are counted for the crate depended on
+                self.dependencies =
+                    Some(read_rows(path, reader, |r: &DependencyRow| {
+                        self.keep_crate(r.crate_id)
+                    })?);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Joins the tables into the format used by the `crates.io` API
+    fn into_db_dump(mut self) -> DbDump {
+        let mut versions_by_crate: HashMap<u64, Vec<Version>> = HashMap::new();
+        for v in self.versions {
+            versions_by_crate
+                .entry(v.crate_id)
+                .or_default()
+                .push(Version {
+                    crate_name: String::new(),
+                    created_at: v.created_at,
+                    updated_at: v.updated_at,
+                    dl_path: String::new(),
+                    downloads: v.downloads,
+                    features: HashMap::new(),
+                    id: v.id,
+                    num: v.num,
+                    yanked: v.yanked,
+                    license: v.license,
+                    readme_path: None,
+                    links: VersionLinks {
+                        #[allow(deprecated)]
+                        authors: String::new(),
+                        dependencies: String::new(),
+                        version_downloads: String::new(),
+                    },
+                    crate_size: v.crate_size,
+                    published_by: v
+                        .published_by
+                        .and_then(|id| self.users.get(&id).cloned()),
+                });
+        }
+
+        let mut owners_by_crate: HashMap<u64, Vec<User>> = HashMap::new();
+        for o in self.crate_owners {
+            let owner = match o.owner_kind {
+                OwnerKind::User => self.users.get(&o.owner_id),
+                OwnerKind::Team => self.teams.get(&o.owner_id),
+            };
+            if let Some(owner) = owner {
+                owners_by_crate
+                    .entry(o.crate_id)
+                    .or_default()
+                    .push(owner.clone());
+            }
+        }
+
+        let mut dump = DbDump {
+            created_at: self.created_at,
+            ..DbDump::default()
+        };
+        let mut crate_names = HashMap::new();
+        for c in self.crates {
+            let mut versions =
+                versions_by_crate.remove(&c.id).unwrap_or_default();
+
+            // The API lists the newest versions first
+            versions.sort_by_key(|v| std::cmp::Reverse(v.created_at));
+            crate_names.insert(c.id, c.name.clone());
+            for v in &mut versions {
+                let api_path = format!("/api/v1/crates/{}/{}", c.name, v.num);
+                v.crate_name = c.name.clone();
+                v.dl_path = format!("{api_path}/download");
+                v.links.dependencies = format!("{api_path}/dependencies");
+                v.links.version_downloads = format!("{api_path}/downloads");
+            }
+
+            let downloads = c
+                .downloads
+                .or_else(|| self.crate_downloads.get(&c.id).copied())
+                .unwrap_or_else(|| versions.iter().map(|v| v.downloads).sum());
+
+            // The dump only contains the downloads of the last 90 days, which
+            // is what is considered recent by the API
+            let version_downloads = self.version_downloads.as_mut().map(|d| {
+                versions
+                    .iter()
+                    .filter_map(|v| d.remove(&v.id))
+                    .flatten()
+                    .collect::<Vec<_>>()
+            });
+            let recent_downloads = version_downloads
+                .as_ref()
+                .map(|d| d.iter().map(|vd| vd.downloads).sum());
+            if let Some(version_downloads) = version_downloads {
+                dump.downloads.get_or_insert_with(HashMap::new).insert(
+                    c.name.clone(),
+                    CrateDownloads {
+                        version_downloads,
+                        meta: CrateDownloadsMeta {
+                            extra_downloads: Vec::new(),
+                        },
+                    },
+                );
+            }
+
+            let api_path = format!("/api/v1/crates/{}", c.name);
+            #[allow(deprecated)]
+            let crate_data = Crate {
+                id: c.name.clone(),
+                name: c.name.clone(),
+                description: c.description,
+                license: None,
+                documentation: c.documentation,
+                homepage: c.homepage,
+                repository: c.repository,
+                downloads,
+                recent_downloads,
+                categories: None,
+                keywords: None,
+                versions: Some(versions.iter().map(|v| v.id).collect()),
+                max_version: latest_version(&versions, false)
+                    .map_or_else(|| String::from("0.0.0"), |v| v.to_string()),
+                max_stable_version: latest_version(&versions, true)
+                    .map(|v| v.to_string()),
+                links: CrateLinks {
+                    owner_team: format!("{api_path}/owner_team"),
+                    owner_user: format!("{api_path}/owner_user"),
+                    owners: format!("{api_path}/owners"),
+                    reverse_dependencies: format!(
+                        "{api_path}/reverse_dependencies"
+                    ),
+                    version_downloads: format!("{api_path}/downloads"),
+                    versions: None,
+                },
+                created_at: c.created_at,
+                updated_at: c.updated_at,
+                exact_match: None,
+            };
+
+            dump.owners.insert(
+                c.name.clone(),
+                owners_by_crate.remove(&c.id).unwrap_or_default(),
+            );
+            dump.crates.insert(
+                c.name,
+                CrateResponse {
+                    categories: Vec::new(),
+                    crate_data,
+                    keywords: Vec::new(),
+                    versions,
+                },
+            );
+        }
+
+        // Only dependencies of the newest version of each crate are counted
+        let newest_versions = self
+            .newest_versions
+            .into_iter()
+            .map(|(crate_id, (_, version_id))| (version_id, crate_id))
+            .collect::<HashMap<_, _>>();
+        dump.reverse_dependencies = self.dependencies.map(|dependencies| {
+            let mut dependents: HashMap<u64, HashSet<u64>> = HashMap::new();
+            for d in dependencies {
+                if let Some(dependent) = newest_versions.get(&d.version_id) {
+                    dependents
+                        .entry(d.crate_id)
+                        .or_default()
+                        .insert(*dependent);
+                }
+            }
+            crate_names
+                .into_iter()
+                .map(|(id, name)| {
+                    let count = dependents.get(&id).map_or(0, HashSet::len);
+                    (name, count as u64)
+                })
+                .collect()
+        });
+
+        dump
+    }
+}
+
+/// Reads the rows of a table in the dump for which `keep` is true, ignoring
+/// unused columns
+fn read_rows<T: DeserializeOwned>(
+    path: &Path,
+    reader: impl Read,
+    mut keep: impl FnMut(&T) -> bool,
+) -> Result<Vec<T>, Box<DbDumpError>> {
+    let mut rows = Vec::new();
+    for row in csv::Reader::from_reader(reader).into_deserialize() {
+        let row = row.map_err(|e| {
+            Box::new(DbDumpError::CouldNotParse(
+                path.to_string_lossy().to_string(),
+                e.to_string(),
+            ))
+        })?;
+        if keep(&row) {
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
+
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    NaiveDateTime::parse_from_str(&raw, TIMESTAMP_FORMAT)
+        .map(|dt| Utc.from_utc_datetime(&dt))
+        .map_err(serde::de::Error::custom)
+}
+
+/// Booleans are stored as `t` and `f`
+fn deserialize_bool<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<bool, D::Error> {
+    match String::deserialize(deserializer)?.as_str() {
+        "t" => Ok(true),
+        "f" => Ok(false),
+        other => Err(serde::de::Error::custom(format!(
+            "expected `t` or `f`, found `{other}`"
+        ))),
+    }
+}
+
+#[derive(Deserialize)]
+struct CrateRow {
+    id: u64,
+    name: String,
+    description: Option<String>,
+    documentation: Option<String>,
+    homepage: Option<String>,
+    repository: Option<String>,
+
+    /// Moved to `crate_downloads.csv` in newer dumps
+    #[serde(default)]
+    downloads: Option<u64>,
+
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    created_at: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct CrateDownloadsRow {
+    crate_id: u64,
+    downloads: u64,
+}
+
+#[derive(Deserialize)]
+struct VersionRow {
+    id: u64,
+    crate_id: u64,
+    num: String,
+    downloads: u64,
+    #[serde(deserialize_with = "deserialize_bool")]
+    yanked: bool,
+    license: Option<String>,
+    crate_size: Option<u64>,
+    published_by: Option<u64>,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    created_at: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct DependencyRow {
+    version_id: u64,
+    crate_id: u64,
+}
+
+#[derive(Deserialize)]
+struct VersionDownloadsRow {
+    date: NaiveDate,
+    version_id: u64,
+    downloads: u64,
+}
+
+#[derive(Deserialize)]
+struct UserRow {
+    id: u64,
+    gh_login: String,
+    name: Option<String>,
+    gh_avatar: Option<String>,
+}
+
+impl From<UserRow> for User {
+    fn from(value: UserRow) -> Self {
+        Self {
+            avatar: value.gh_avatar,
+            email: None,
+            id: value.id,
+            kind: Some(String::from("user")),
+            url: format!("https://github.com/{}", value.gh_login),
+            login: value.gh_login,
+            name: value.name,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TeamRow {
+    id: u64,
+
+    /// Logins of teams are on the form `github:org:team`
+    login: String,
+    name: Option<String>,
+    avatar: Option<String>,
+}
+
+impl From<TeamRow> for User {
+    fn from(value: TeamRow) -> Self {
+        let org = value.login.split(':').nth(1).unwrap_or_default();
+        Self {
+            avatar: value.avatar,
+            email: None,
+            id: value.id,
+            kind: Some(String::from("team")),
+            url: format!("https://github.com/{org}"),
+            login: value.login,
+            name: value.name,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+enum OwnerKind {
+    #[serde(rename = "0")]
+    User,
+    #[serde(rename = "1")]
+    Team,
+}
+
+#[derive(Deserialize)]
+struct CrateOwnerRow {
+    crate_id: u64,
+    owner_id: u64,
+    owner_kind: OwnerKind,
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashSet, fs::File, io::Read, path::Path};
+
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use test_case::test_case;
+
+    use super::DbDump;
+
+    const DB_DUMP_PATH: &str = "test_data/crates_io_db_dump.tar.gz";
+
+    /// Repacks the test dump with `crates.csv` first, so the crates to keep
+    /// are known before the other tables are read
+    fn crates_first_db_dump() -> Vec<u8> {
+        let mut archive = tar::Archive::new(GzDecoder::new(
+            File::open(DB_DUMP_PATH).unwrap(),
+        ));
+        let mut entries = archive
+            .entries()
+            .unwrap()
+            .map(|e| {
+                let mut e = e.unwrap();
+                let mut data = Vec::new();
+                e.read_to_end(&mut data).unwrap();
+                (e.header().clone(), e.path().unwrap().into_owned(), data)
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(_, path, _)| !path.ends_with("crates.csv"));
+
+        let mut builder =
+            tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+        for (mut header, path, data) in entries {
+            builder
+                .append_data(&mut header, path, data.as_slice())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test_case(false ; "crates last")]
+    #[test_case(true ; "crates first")]
+    fn only_crates_in_graph(crates_first: bool) {
+        let crate_names = HashSet::from([String::from("unicode-ident")]);
+        let mut dump = if crates_first {
+            DbDump::from_reader(
+                crates_first_db_dump().as_slice(),
+                Some(&crate_names),
+            )
+        } else {
+            DbDump::from_path(Path::new(DB_DUMP_PATH), Some(&crate_names))
+        }
+        .unwrap();
+        let mut full_dump =
+            DbDump::from_path(Path::new(DB_DUMP_PATH), None).unwrap();
+
+        assert!(dump.take_crate("libc").is_none());
+        assert!(dump.take_owners("proc-macro2").is_none());
+        assert_eq!(
+            dump.take_crate("unicode-ident").unwrap().versions.len(),
+            full_dump
+                .take_crate("unicode-ident")
+                .unwrap()
+                .versions
+                .len()
+        );
+        assert_eq!(
+            dump.take_downloads("unicode-ident")
+                .unwrap()
+                .version_downloads
+                .len(),
+            full_dump
+                .take_downloads("unicode-ident")
+                .unwrap()
+                .version_downloads
+                .len()
+        );
+
+        // Dependents are counted among all crates, not only those kept
+        assert_eq!(
+            dump.reverse_dependencies_count("unicode-ident"),
+            full_dump.reverse_dependencies_count("unicode-ident")
+        );
+        assert_eq!(dump.reverse_dependencies_count("libc"), None);
+    }
+}
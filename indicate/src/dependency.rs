@@ -0,0 +1,190 @@
+//! Types describing how one package depends on another, combining the resolved
+//! dependency graph with what was declared in the `Cargo.toml` manifest
+
+use std::rc::Rc;
+
+use cargo_metadata::{Dependency, DependencyKind, Package};
+use cargo_platform::Platform;
+use cfg_expr::{targets::TargetInfo, Expression, Predicate};
+
+/// A resolved dependency from one package to another
+///
+/// A package may depend on the same package several times, such as both as a
+/// normal dependency and as a build dependency, or for different targets. Each
+/// of these will be a separate edge.
+#[derive(Debug, Clone)]
+pub struct DependencyEdge {
+    kind: DependencyKind,
+    target: Option<Platform>,
+
+    /// The dependency as declared in the manifest of the dependent package
+    ///
+    /// Should always be possible to find, but if the resolve graph and the
+    /// manifest disagree this will be `None`.
+    declaration: Option<Dependency>,
+    package: Rc<Package>,
+}
+
+impl DependencyEdge {
+    #[must_use]
+    pub fn new(
+        kind: DependencyKind,
+        target: Option<Platform>,
+        declaration: Option<Dependency>,
+        package: Rc<Package>,
+    ) -> Self {
+        Self {
+            kind,
+            target,
+            declaration,
+            package,
+        }
+    }
+
+    /// The kind of dependency, i.e. normal, dev or build
+    #[must_use]
+    pub fn kind(&self) -> DependencyKind {
+        self.kind
+    }
+
+    /// The platform this dependency is limited to, if any, such as
+    /// `cfg(windows)` or `x86_64-unknown-linux-gnu`
+    #[must_use]
+    pub fn target(&self) -> Option<&Platform> {
+        self.target.as_ref()
+    }
+
+    /// Checks if this dependency is used when compiling for a target, i.e. it
+    /// has no platform requirement or its `cfg` expression or target triple
+    /// matches the target
+    ///
+    /// Only predicates about the target are evaluated, so `cfg(test)`,
+    /// `cfg(feature = "...")` etc. are never considered to be active.
+    #[must_use]
+    pub fn active_on_target(&self, target: &TargetInfo) -> bool {
+        match &self.target {
+            None => true,
+            Some(Platform::Name(triple)) => triple == target.triple.as_str(),
+            Some(Platform::Cfg(cfg)) => Expression::parse(&cfg.to_string())
+                .is_ok_and(|expr| {
+                    expr.eval(|predicate| match predicate {
+                        Predicate::Target(tp) => tp.matches(target),
+                        _ => false,
+                    })
+                }),
+        }
+    }
+
+    /// If this dependency is only included when a feature enables it
+    #[must_use]
+    pub fn optional(&self) -> bool {
+        self.declaration.as_ref().is_some_and(|d| d.optional)
+    }
+
+    /// If the default features of the dependency are enabled
+    #[must_use]
+    pub fn uses_default_features(&self) -> bool {
+        self.declaration
+            .as_ref()
+            .is_none_or(|d| d.uses_default_features)
+    }
+
+    /// Features explicitly enabled for this dependency by the dependent
+    /// package
+    #[must_use]
+    pub fn enabled_features(&self) -> &[String] {
+        self.declaration
+            .as_ref()
+            .map_or(&[], |d| d.features.as_slice())
+    }
+
+    /// The name this dependency was renamed to, if it was renamed using the
+    /// `package` key in `Cargo.toml`
+    #[must_use]
+    pub fn renamed_as(&self) -> Option<&str> {
+        self.declaration.as_ref().and_then(|d| d.rename.as_deref())
+    }
+
+    /// The package that this edge points to
+    #[must_use]
+    pub fn package(&self) -> Rc<Package> {
+        Rc::clone(&self.package)
+    }
+}
+
+/// A chain of normal dependencies from a root package to some package
+#[derive(Debug, Clone)]
+pub struct DependencyPath {
+    /// Ordered from the root package to the target package, both included
+    packages: Vec<Rc<Package>>,
+}
+
+impl DependencyPath {
+    #[must_use]
+    pub fn new(packages: Vec<Rc<Package>>) -> Self {
+        Self { packages }
+    }
+
+    /// The number of dependency edges in this path, i.e. `0` for a path from
+    /// the root package to itself
+    #[must_use]
+    pub fn length(&self) -> usize {
+        self.packages.len().saturating_sub(1)
+    }
+
+    /// Names of the packages along this path, starting with the root package
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        self.packages.iter().map(|p| p.name.clone()).collect()
+    }
+
+    #[must_use]
+    pub fn packages(&self) -> &[Rc<Package>] {
+        &self.packages
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{rc::Rc, str::FromStr};
+
+    use cargo_metadata::{DependencyKind, Package};
+    use cargo_platform::Platform;
+    use cfg_expr::targets::get_builtin_target_by_triple;
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::DependencyEdge;
+
+    const LINUX: &str = "x86_64-unknown-linux-gnu";
+    const WINDOWS: &str = "x86_64-pc-windows-msvc";
+
+    #[test_case(None, LINUX => true ; "no platform requirement")]
+    #[test_case(Some("cfg(windows)"), LINUX => false ; "windows cfg on linux")]
+    #[test_case(Some("cfg(windows)"), WINDOWS => true ; "windows cfg on windows")]
+    #[test_case(Some("cfg(all(unix, target_arch = \"x86_64\"))"), LINUX => true ; "combined cfg on linux")]
+    #[test_case(Some("cfg(not(target_os = \"linux\"))"), LINUX => false ; "negated cfg on linux")]
+    #[test_case(Some("cfg(test)"), LINUX => false ; "non target cfg")]
+    #[test_case(Some(WINDOWS), WINDOWS => true ; "matching triple")]
+    #[test_case(Some(WINDOWS), LINUX => false ; "other triple")]
+    fn active_on_target(platform: Option<&str>, triple: &str) -> bool {
+        // Only the platform matters, so any package will do
+        let package: Package = serde_json::from_value(json!({
+            "name": "libc",
+            "version": "0.2.139",
+            "id": "libc 0.2.139 (registry+https://github.com/rust-lang/crates.io-index)",
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+            "manifest_path": "/libc-0.2.139/Cargo.toml",
+        }))
+        .unwrap();
+        let edge = DependencyEdge::new(
+            DependencyKind::Normal,
+            platform.map(|p| Platform::from_str(p).unwrap()),
+            None,
+            Rc::new(package),
+        );
+        edge.active_on_target(get_builtin_target_by_triple(triple).unwrap())
+    }
+}
@@ -0,0 +1,46 @@
+//! Types used to find crates that resolve to more than one version in the
+//! dependency graph
+
+use std::rc::Rc;
+
+use cargo_metadata::Package;
+
+/// All packages in the dependency graph sharing the same crate name, but with
+/// different versions
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    name: String,
+
+    /// Sorted by version, lowest first
+    packages: Rc<Vec<Rc<Package>>>,
+}
+
+impl DuplicateGroup {
+    #[must_use]
+    pub fn new(name: String, packages: Rc<Vec<Rc<Package>>>) -> Self {
+        Self { name, packages }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The distinct versions of this crate in the dependency graph, lowest
+    /// first
+    #[must_use]
+    pub fn versions(&self) -> Vec<String> {
+        let mut versions = self
+            .packages
+            .iter()
+            .map(|p| p.version.to_string())
+            .collect::<Vec<_>>();
+        versions.dedup();
+        versions
+    }
+
+    #[must_use]
+    pub fn packages(&self) -> Rc<Vec<Rc<Package>>> {
+        Rc::clone(&self.packages)
+    }
+}
@@ -32,3 +32,38 @@ pub enum ManifestPathError {
     #[error("could not create a valid absoulute path to a `Cargo`.toml file: Created `{0}")]
     CouldNotCreateValidPath(String),
 }
+
+#[derive(Error, Debug, Clone)]
+pub enum LockfileError {
+    #[error("could not read lockfile `{0}` due to error: {1}")]
+    CouldNotRead(String, String),
+
+    #[error("could not parse lockfile `{0}` due to error: {1}")]
+    CouldNotParse(String, String),
+
+    #[error("dependency `{0}` of package `{1}` is not part of the lockfile")]
+    UnknownDependency(String, String),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum DbDumpError {
+    #[error("could not read crates.io database dump `{0}` due to error: {1}")]
+    CouldNotRead(String, String),
+
+    #[error(
+        "could not parse crates.io database table `{0}` due to error: {1}"
+    )]
+    CouldNotParse(String, String),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum RegistryError {
+    #[error("could not read registry configuration `{0}` due to error: {1}")]
+    CouldNotRead(String, String),
+
+    #[error("could not parse `{0}` due to error: {1}")]
+    CouldNotParse(String, String),
+
+    #[error("request to `{0}` failed due to error: {1}")]
+    RequestFailed(String, String),
+}
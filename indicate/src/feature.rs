@@ -0,0 +1,140 @@
+//! Types used to describe the features of a package, and what they enable
+//!
+//! See [the Cargo book](https://doc.rust-lang.org/cargo/reference/features.html)
+//! for the syntax of feature definitions.
+
+use std::{collections::HashSet, rc::Rc};
+
+use cargo_metadata::Package;
+
+/// A feature declared by a package, including implicit features created by
+/// optional dependencies
+#[derive(Debug, Clone)]
+pub struct Feature {
+    package: Rc<Package>,
+    name: String,
+
+    /// If this feature was enabled when resolving the dependency graph
+    enabled: bool,
+}
+
+impl Feature {
+    #[must_use]
+    pub fn new(package: Rc<Package>, name: String, enabled: bool) -> Self {
+        Self {
+            package,
+            name,
+            enabled,
+        }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The package declaring this feature
+    #[must_use]
+    pub fn package(&self) -> Rc<Package> {
+        Rc::clone(&self.package)
+    }
+
+    /// The entries of this feature definition, as written in `Cargo.toml`
+    #[must_use]
+    pub fn values(&self) -> Vec<FeatureValue<'_>> {
+        self.package
+            .features
+            .get(&self.name)
+            .map(|v| v.iter().map(|s| FeatureValue::from(s.as_str())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Checks if this feature is enabled by the `default` feature of its
+    /// package, directly or via other features
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec!["default"];
+
+        while let Some(f) = stack.pop() {
+            if f == self.name {
+                return true;
+            }
+
+            if !visited.insert(f) {
+                continue;
+            }
+
+            for v in self.package.features.get(f).into_iter().flatten() {
+                if let FeatureValue::Feature(name) =
+                    FeatureValue::from(v.as_str())
+                {
+                    stack.push(name);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// A single entry in a feature definition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureValue<'a> {
+    /// Another feature of the same package, like `"std"`
+    Feature(&'a str),
+
+    /// An optional dependency, like `"dep:libc"`
+    Dependency(&'a str),
+
+    /// A feature of a dependency, like `"libc/std"`, or `"libc?/std"` if it
+    /// is weak (i.e. does not enable the dependency itself if optional)
+    DependencyFeature {
+        dependency: &'a str,
+        feature: &'a str,
+        weak: bool,
+    },
+}
+
+impl<'a> From<&'a str> for FeatureValue<'a> {
+    fn from(value: &'a str) -> Self {
+        if let Some(dependency) = value.strip_prefix("dep:") {
+            FeatureValue::Dependency(dependency)
+        } else if let Some((dependency, feature)) = value.split_once('/') {
+            match dependency.strip_suffix('?') {
+                Some(dependency) => FeatureValue::DependencyFeature {
+                    dependency,
+                    feature,
+                    weak: true,
+                },
+                None => FeatureValue::DependencyFeature {
+                    dependency,
+                    feature,
+                    weak: false,
+                },
+            }
+        } else {
+            FeatureValue::Feature(value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::FeatureValue;
+
+    #[test_case("std" => FeatureValue::Feature("std") ; "feature")]
+    #[test_case("dep:libc" => FeatureValue::Dependency("libc") ; "optional dependency")]
+    #[test_case("libc/std" => FeatureValue::DependencyFeature { dependency: "libc", feature: "std", weak: false } ; "dependency feature")]
+    #[test_case("libc?/std" => FeatureValue::DependencyFeature { dependency: "libc", feature: "std", weak: true } ; "weak dependency feature")]
+    fn parse_feature_value(value: &str) -> FeatureValue<'_> {
+        FeatureValue::from(value)
+    }
+}
@@ -44,22 +44,26 @@
 
 use std::{
     collections::HashMap,
+    hash::{Hash, Hasher},
     ops::Add,
     process::{Command, Stdio},
 };
 
-use cargo_metadata::CargoOpt;
+use cargo_metadata::{CargoOpt, Package};
+use rustsec::Version;
 use serde::Deserialize;
+use url::Url;
 
-use crate::{errors::GeigerError, ManifestPath, NameVersion};
+use crate::{errors::GeigerError, source::Source, ManifestPath};
 
 /// A client used to evaluate `cargo-geiger` information for some package
 /// and its dependencies
 #[derive(Debug)]
 pub struct GeigerClient {
     #[cfg(test)]
+    #[allow(dead_code)]
     output: GeigerOutput,
-    unsafety: HashMap<NameVersion, GeigerUnsafety>,
+    unsafety: HashMap<GeigerPackageId, GeigerUnsafety>,
 }
 
 impl GeigerClient {
@@ -158,9 +162,11 @@ impl GeigerClient {
         Ok(Self::from(output))
     }
 
+    /// Retrieves the unsafety of a package, matching on its name, version and
+    /// source
     #[must_use]
-    pub fn unsafety(&self, gid: &NameVersion) -> Option<GeigerUnsafety> {
-        self.unsafety.get(gid).copied()
+    pub fn unsafety(&self, package: &Package) -> Option<GeigerUnsafety> {
+        self.unsafety.get(&GeigerPackageId::from(package)).copied()
     }
 }
 
@@ -209,10 +215,123 @@ pub struct GeigerPackageOutput {
 /// A package in `cargo-geiger` used to identify what has been parsed
 #[derive(Debug, Clone, Deserialize)]
 pub struct GeigerPackage {
-    pub id: NameVersion,
+    pub id: GeigerPackageId,
     // Other fields ignored
 }
 
+/// The full id of a package in `cargo-geiger`, used to tell apart packages
+/// with the same name and version from different sources
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Hash)]
+pub struct GeigerPackageId {
+    pub name: String,
+    pub version: Version,
+    pub source: GeigerSource,
+}
+
+impl From<&Package> for GeigerPackageId {
+    fn from(package: &Package) -> Self {
+        Self {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            source: GeigerSource::from(package),
+        }
+    }
+}
+
+/// Where a package in `cargo-geiger` comes from
+///
+/// URLs are kept as strings, as written by `cargo` itself.
+#[derive(Debug, Clone, Deserialize)]
+pub enum GeigerSource {
+    /// A git repository, with `rev` being the locked commit
+    Git {
+        url: String,
+        rev: String,
+    },
+    Registry {
+        name: String,
+        url: String,
+    },
+
+    /// A local path, with a `file://` URL to the package directory
+    Path(String),
+}
+
+impl PartialEq for GeigerSource {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                GeigerSource::Git { url, rev },
+                GeigerSource::Git {
+                    url: other_url,
+                    rev: other_rev,
+                },
+            ) => url == other_url && rev == other_rev,
+            // The registry name is not part of `cargo metadata`, so a
+            // registry is identified by its URL alone
+            (
+                GeigerSource::Registry { url, .. },
+                GeigerSource::Registry { url: other_url, .. },
+            ) => url == other_url,
+            (GeigerSource::Path(url), GeigerSource::Path(other_url)) => {
+                url == other_url
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for GeigerSource {}
+
+impl Hash for GeigerSource {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            GeigerSource::Git { url, rev } => {
+                url.hash(state);
+                rev.hash(state);
+            }
+            GeigerSource::Registry { url, .. } | GeigerSource::Path(url) => {
+                url.hash(state);
+            }
+        }
+    }
+}
+
+impl From<&Package> for GeigerSource {
+    fn from(package: &Package) -> Self {
+        match &package.source {
+            Some(s) if s.repr.starts_with("git+") => {
+                let source = Source::parse(&s.repr);
+                GeigerSource::Git {
+                    url: source.url().unwrap_or_default().to_string(),
+                    rev: source.locked_commit().unwrap_or_default().to_string(),
+                }
+            }
+            Some(s) => GeigerSource::Registry {
+                name: String::new(),
+                // Sparse registries keep their prefix in the URL
+                url: s
+                    .repr
+                    .strip_prefix("registry+")
+                    .unwrap_or(&s.repr)
+                    .to_string(),
+            },
+            None => {
+                let dir = package
+                    .manifest_path
+                    .parent()
+                    .expect("manifest path has no parent directory");
+                GeigerSource::Path(
+                    Url::from_file_path(dir)
+                        .map(String::from)
+                        .unwrap_or_else(|()| format!("file://{dir}")),
+                )
+            }
+        }
+    }
+}
+
 /// The output of `cargo-geiger` for one package/dependency
 ///
 /// Corresponds to the object named "unsafety" in a `cargo-geiger` output.
@@ -376,11 +495,15 @@ impl Add<GeigerCount> for GeigerCount {
 mod test {
     use std::{fs, path::Path};
 
+    use cargo_metadata::Package;
+    use serde_json::json;
     use test_case::test_case;
 
     use crate::{geiger::GeigerCount, ManifestPath};
 
-    use super::{GeigerClient, GeigerOutput};
+    use rustsec::Version;
+
+    use super::{GeigerClient, GeigerOutput, GeigerPackageId, GeigerSource};
 
     #[test_case(0, 0 => 0.0)]
     #[test_case(3, 1 => 25.0)]
@@ -409,6 +532,57 @@ mod test {
         serde_json::from_str::<GeigerOutput>(&json_string).unwrap();
     }
 
+    #[test]
+    fn registry_name_ignored_in_package_id() {
+        let id = |name: &str| GeigerPackageId {
+            name: String::from("libc"),
+            version: Version::new(0, 2, 139),
+            source: GeigerSource::Registry {
+                name: String::from(name),
+                url: String::from(
+                    "https://github.com/rust-lang/crates.io-index",
+                ),
+            },
+        };
+        assert_eq!(id("crates.io"), id(""));
+    }
+
+    #[test]
+    fn path_and_registry_package_ids_differ() {
+        let url = String::from("https://github.com/rust-lang/crates.io-index");
+        let registry = GeigerSource::Registry {
+            name: String::from("crates.io"),
+            url: url.clone(),
+        };
+        assert_ne!(registry, GeigerSource::Path(url));
+    }
+
+    #[test]
+    fn sparse_registry_package_id_matches_metadata() {
+        let json_string =
+            fs::read_to_string("test_data/geiger-output/sparse_registry.json")
+                .unwrap();
+        let output =
+            serde_json::from_str::<GeigerOutput>(&json_string).unwrap();
+
+        let package: Package = serde_json::from_value(json!({
+            "name": "unicode-ident",
+            "version": "1.0.6",
+            "id": "unicode-ident 1.0.6 (sparse+https://index.crates.io/)",
+            "source": "sparse+https://index.crates.io/",
+            "dependencies": [],
+            "targets": [],
+            "features": {},
+            "manifest_path": "/unicode-ident-1.0.6/Cargo.toml",
+        }))
+        .unwrap();
+
+        assert_eq!(
+            output.packages[0].package.id,
+            GeigerPackageId::from(&package)
+        );
+    }
+
     #[test_case(0, 0, 0, 0)]
     #[test_case(1, 1, 0, 0)]
     #[test_case(1, 2, 3, 4)]
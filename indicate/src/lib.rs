@@ -27,16 +27,26 @@ pub mod adapter;
 pub mod advisory;
 pub mod code_stats;
 pub mod crates_io;
+pub mod dependency;
+pub mod duplicate;
 pub mod errors;
+pub mod feature;
 pub mod geiger;
+pub mod license;
+pub mod lockfile;
 pub mod manifest;
 pub mod query;
+pub mod registry;
+pub mod registry_index;
 pub mod repo;
+pub mod source;
 pub mod util;
 mod vertex;
 
 /// Features to create metadata with
 pub use cargo_metadata::CargoOpt;
+/// Metadata of a package, as provided by `cargo metadata`
+pub use cargo_metadata::Metadata;
 pub use rustsec::advisory::Severity;
 /// Valid platforms that can be provided to queries
 pub use rustsec::platforms;
@@ -69,7 +79,7 @@ static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
 pub struct NameVersion {
     pub name: String,
     pub version: Version,
-    // Other fields ignored, assume crates.io registry
+    // Other fields ignored, only used for packages from the crates.io registry
 }
 
 impl NameVersion {
@@ -142,7 +152,7 @@ pub fn execute_query_with_adapter(
 #[cfg(test)]
 mod test {
     // use lazy_static::lazy_static;
-    use cargo_metadata::CargoOpt;
+    use cargo_metadata::{CargoOpt, Metadata};
     use core::panic;
     use std::{
         collections::BTreeMap,
@@ -156,19 +166,20 @@ mod test {
 
     use crate::{
         adapter::IndicateAdapter, advisory::AdvisoryClient,
-        execute_query_with_adapter, query::FullQuery,
-        repo::github::GH_API_CALL_COUNTER, util::transparent_results,
-        IndicateAdapterBuilder, ManifestPath,
+        execute_query_with_adapter, lockfile::metadata_from_lockfile,
+        query::FullQuery, registry::RegistryConfig,
+        registry_index::RegistryIndexClient, repo::github::GH_API_CALL_COUNTER,
+        util::transparent_results, IndicateAdapterBuilder, ManifestPath,
     };
 
     /// File that may never exist, to ensure some test work
-    const NONEXISTENT_FILE: &'static str = "test_data/notafile";
+    const NONEXISTENT_FILE: &str = "test_data/notafile";
 
     /// Retrieve paths for the crate and query names provided, relative to
     /// `indicate` crate root
-    fn get_paths<'a, 'b>(
-        fake_crate_name: &'a str,
-        query_name: &'b str,
+    fn get_paths(
+        fake_crate_name: &str,
+        query_name: &str,
     ) -> (PathBuf, PathBuf) {
         let raw_cargo_toml_path =
             format!("test_data/fake_crates/{fake_crate_name}/Cargo.toml");
@@ -269,6 +280,35 @@ mod test {
     #[test_case("dev_deps", "dev_dependencies_excluded" ; "dev-dependencies excluded in dep resolution when using Dependencies entry point")]
     #[test_case("dev_deps", "dev_dependencies_excluded_w_root_package" ; "dev-dependencies excluded in dep resolution when using RootPackage entry point")]
     #[test_case("transitive_deps", "list_transitive_dependencies" ; "list only transitive dependencies")]
+    #[test_case("transitive_deps", "dependents" ; "direct and transitive dependents of dependencies")]
+    #[test_case("dependency_kinds", "dependency_edges" ; "dependency edges of all kinds with manifest information")]
+    #[test_case("dependency_kinds", "dependencies_build_dev_kinds" ; "dependencies of only build and dev kinds")]
+    #[test_case("dependency_kinds", "direct_build_dependencies" ; "direct build dependencies of root package")]
+    #[test_case("build_deps", "build_dependencies_transitive" ; "normal dependencies of build dependencies")]
+    #[test_case("build_deps", "build_dependencies_transitive_member" ; "normal dependencies of build dependencies of a member")]
+    #[test_case("feature_deps", "package_features" ; "features of root package and what they activate")]
+    #[test_case("package_targets", "package_targets" ; "targets of root package")]
+    #[test_case("manifest_fields", "manifest_fields" ; "all manifest metadata fields of packages")]
+    #[test_case("sources", "package_sources" ; "registry and path sources of packages")]
+    #[test_case("shadowed_crates", "shadowed_crates_io" ; "no crates io or advisory data for path packages")]
+    #[test_case("virtual_workspace", "workspace_members" ; "members of a virtual workspace and their dependencies")]
+    #[test_case("virtual_workspace", "workspace_dependencies" ; "all dependencies of a virtual workspace including its members")]
+    #[test_case("virtual_workspace", "workspace_member_dependencies" ; "dependencies of a single workspace member")]
+    #[test_case("virtual_workspace", "workspace_unknown_member_dependencies" ; "no dependencies of an unknown workspace member")]
+    #[test_case("virtual_workspace", "virtual_workspace_root_package" ; "no root package in a virtual workspace")]
+    #[test_case("duplicated_deps", "duplicated_packages" ; "crates resolving to more than one version")]
+    #[test_case("duplicated_deps", "other_versions_in_graph" ; "other versions of a package in the dependency graph")]
+    #[test_case("same_version_sources", "duplicated_packages_same_version" ; "same version from different sources is not duplicated")]
+    #[test_case("same_version_sources", "other_versions_same_version" ; "same version from a different source is not another version")]
+    #[test_case("duplicated_deps", "dependency_edges_renamed" ; "dependency edges of a renamed second copy of a crate")]
+    #[test_case("lib_names", "dependency_edges_lib_name" ; "dependency edges of a library named differently than its package")]
+    #[test_case("transitive_deps", "paths_from_root" ; "all dependency paths from the root package and depth")]
+    #[test_case("transitive_deps", "shortest_path_from_root" ; "only the shortest dependency path from the root package")]
+    #[test_case("dependency_kinds", "dependency_edges_on_target" ; "dependency edges active on a target triple")]
+    #[test_case("licenses", "license_expressions" ; "parsed license expressions checked against an allow list")]
+    #[test_case("licenses", "license_files" ; "licenses detected from license files")]
+    #[test_case("licenses", "license_conflicts" ; "dependencies with licenses incompatible with the root package")]
+    #[test_case("licenses", "license_compatible_with" ; "license compatibility with a provided root license")]
     #[test_case("simple_deps", "code_stats_simple")]
     #[test_case("simple_deps", "all_deps_code_stats")]
     #[test_case("simple_deps", "all_deps_code_stats_only_src")]
@@ -351,6 +391,60 @@ mod test {
         assert_query_res(res, expected_result_path);
     }
 
+    /// Creates metadata from the `Cargo.lock` of a fake crate, reading
+    /// registry manifests from a fake `CARGO_HOME`
+    fn lockfile_metadata(fake_crate_name: &str) -> Metadata {
+        metadata_from_lockfile(
+            Path::new(&format!(
+                "test_data/fake_crates/{fake_crate_name}/Cargo.lock"
+            )),
+            Some(Path::new("test_data/fake_cargo_home")),
+        )
+        .unwrap()
+    }
+
+    /// Test queries using an adapter customized by the test case, where
+    /// `customize` is passed a builder for the manifest of the fake crate
+    ///
+    /// Metadata is read from the lockfile of the fake crate where data from
+    /// the registry is tested, to not depend on it. The expected result is
+    /// read from `queries_expected/<expected_name>.expected.json`.
+    #[test_case("dependency_kinds", "target_platform_dependencies", "target_platform_dependencies-x86_64-unknown-linux-gnu", |b| b.target_platform(String::from("x86_64-unknown-linux-gnu")) ; "windows dependency excluded on linux")]
+    #[test_case("dependency_kinds", "target_platform_dependencies", "target_platform_dependencies-x86_64-pc-windows-msvc", |b| b.target_platform(String::from("x86_64-pc-windows-msvc")) ; "windows dependency included on windows")]
+    #[test_case("lockfile_deps", "lockfile_dependencies", "lockfile_dependencies", |b| b.metadata(lockfile_metadata("lockfile_deps")) ; "dependencies and manifest information from lockfile")]
+    #[test_case("lockfile_git_deps", "lockfile_git_dependencies", "lockfile_git_dependencies", |b| b.metadata(lockfile_metadata("lockfile_git_deps")) ; "manifest information of git dependencies from lockfile")]
+    #[test_case("lockfile_deps", "lockfile_package_names", "lockfile_package_names", |_| IndicateAdapterBuilder::from_lockfile(Path::new("test_data/fake_crates/lockfile_deps/Cargo.lock")).unwrap() ; "package names and versions from lockfile with real cargo home")]
+    #[test_case("lockfile_deps", "crates_io_db_dump", "crates_io_db_dump", |b| b.metadata(lockfile_metadata("lockfile_deps")).crates_io_db_dump(PathBuf::from("test_data/crates_io_db_dump.tar.gz")) ; "crates.io data from database dump")]
+    #[test_case("lockfile_deps", "crates_io_db_dump_metadata", "crates_io_db_dump_metadata", |b| b.metadata(lockfile_metadata("lockfile_deps")).crates_io_db_dump(PathBuf::from("test_data/crates_io_db_dump.tar.gz")) ; "crates.io metadata from database dump")]
+    #[test_case("lockfile_deps", "crates_io_db_dump_downloads", "crates_io_db_dump_downloads", |b| b.metadata(lockfile_metadata("lockfile_deps")).crates_io_db_dump(PathBuf::from("test_data/crates_io_db_dump.tar.gz")) ; "crates.io downloads from database dump")]
+    #[test_case("lockfile_deps", "registry_index", "registry_index", |b| b.metadata(lockfile_metadata("lockfile_deps")).registry_index(RegistryIndexClient::new(Path::new("test_data/fake_registry_index"))) ; "versions from sparse index mirror")]
+    #[test_case("lockfile_deps", "registry_index", "registry_index", |b| b.metadata(lockfile_metadata("lockfile_deps")).registry_index(RegistryIndexClient::new(Path::new("test_data/fake_cargo_home/registry/index/index.crates.io-6f17d22bba15001f"))) ; "versions from cargo index cache")]
+    #[test_case("alt_registry", "alt_registry", "alt_registry", |b| b.metadata(lockfile_metadata("alt_registry")).registry_index(RegistryIndexClient::new(Path::new("test_data/fake_registry_index"))).registry(RegistryConfig::new("https://my-registry.example.com/index").index_path(PathBuf::from("test_data/fake_alt_registry_index"))) ; "alternative registry configured explicitly and in cargo config")]
+    fn builder_query_test(
+        fake_crate_name: &str,
+        query_name: &str,
+        expected_name: &str,
+        customize: impl FnOnce(IndicateAdapterBuilder) -> IndicateAdapterBuilder,
+    ) {
+        let (cargo_toml_path, query_path) =
+            get_paths(fake_crate_name, query_name);
+        let raw_expected_result_name =
+            format!("test_data/queries_expected/{expected_name}.expected.json");
+        let expected_result_path = Path::new(&raw_expected_result_name);
+
+        let adapter = customize(IndicateAdapterBuilder::new(
+            ManifestPath::new(&cargo_toml_path),
+        ))
+        .build();
+        let res = transparent_results(execute_query_with_adapter(
+            &FullQuery::from_path(&query_path).unwrap(),
+            Rc::new(adapter),
+            None,
+        ));
+
+        assert_query_res(res, expected_result_path);
+    }
+
     #[test_case("test_data/fake_crates/simple_deps" ; "extract from directory")]
     #[test_case("test_data/fake_crates/simple_deps/Cargo.toml" ; "extract from direct path")]
     #[test_case(NONEXISTENT_FILE => panics ; "extract from directory without Cargo.toml")]
@@ -363,7 +457,7 @@ mod test {
     fn extract_query(path_str: &str) {
         let q = FullQuery::from_path(Path::new(path_str));
         match q {
-            Ok(_) => return,
+            Ok(_) => (),
             Err(b) => panic!("{}", b),
         }
     }
@@ -0,0 +1,614 @@
+//! Types describing the license of a package, parsed as an
+//! [SPDX license expression](https://spdx.github.io/spdx-spec/v2.3/SPDX-license-expressions/)
+//! or detected from the license files of the package
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use once_cell::sync::Lazy;
+use spdx::{Expression, LicenseId, LicenseItem, LicenseReq, ParseMode};
+
+/// Prefixes of file names (ignoring case) considered to be license files,
+/// like `LICENSE-MIT` or `COPYING.txt`
+const LICENSE_FILE_PREFIXES: [&str; 4] =
+    ["LICENSE", "LICENCE", "COPYING", "NOTICE"];
+
+/// The lowest similarity to a license text for a file to be considered to
+/// contain that license
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Word pairs of the texts of all non-deprecated SPDX licenses, bundled with
+/// the `spdx` crate
+static LICENSE_CORPUS: Lazy<Vec<(LicenseId, HashSet<u64>)>> = Lazy::new(|| {
+    spdx::identifiers::LICENSES
+        .iter()
+        .filter_map(|(name, _, _)| spdx::license_id(name))
+        .filter(|id| !id.is_deprecated())
+        .map(|id| (id, word_pairs(id.text())))
+        .collect()
+});
+
+/// The `license` field of a package manifest, parsed as an SPDX license
+/// expression
+///
+/// Many older crates use non-standard expressions like `MIT/Apache-2.0`, so
+/// expressions that are not valid SPDX are also parsed using the same lax rules
+/// as crates.io.
+#[derive(Debug, Clone)]
+pub struct LicenseExpression {
+    raw: String,
+
+    /// `None` if the expression could not be parsed even with lax rules
+    expression: Option<Expression>,
+    is_valid_spdx: bool,
+
+    /// Licenses that are allowed to be used, if they are to be checked
+    allowed: Option<Vec<String>>,
+
+    /// The license of the root package, if compatibility with it is to be
+    /// checked
+    root: Option<RootLicense>,
+}
+
+impl LicenseExpression {
+    #[must_use]
+    pub fn new(raw: &str, allowed: Option<Vec<String>>) -> Self {
+        let (expression, is_valid_spdx) = match Expression::parse(raw) {
+            Ok(e) => (Some(e), true),
+            Err(_) => (Expression::parse_mode(raw, ParseMode::LAX).ok(), false),
+        };
+
+        Self {
+            raw: raw.to_string(),
+            expression,
+            is_valid_spdx,
+            allowed,
+            root: None,
+        }
+    }
+
+    /// Sets the license of the root package to check compatibility with, see
+    /// [`LicenseExpression::is_compatible_with_root`]
+    ///
+    /// A root package without a license is treated as proprietary, i.e. as if
+    /// it has a permissive license.
+    #[must_use]
+    pub fn with_root_license(mut self, root_license: Option<&str>) -> Self {
+        self.root = Some(root_license.map_or_else(RootLicense::default, |l| {
+            RootLicense::new(&LicenseExpression::new(l, None))
+        }));
+        self
+    }
+
+    /// The expression as written in the manifest
+    #[must_use]
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// If this expression follows the SPDX specification, i.e. it was not
+    /// only possible to parse using lax rules
+    #[must_use]
+    pub fn is_valid_spdx(&self) -> bool {
+        self.is_valid_spdx
+    }
+
+    /// All SPDX licenses mentioned in this expression, without duplicates
+    ///
+    /// References to licenses not on the SPDX license list, like
+    /// `LicenseRef-Proprietary`, are not included.
+    #[must_use]
+    pub fn licenses(&self) -> Vec<License> {
+        let mut licenses: Vec<License> = Vec::new();
+        for req in self.expression.iter().flat_map(Expression::requirements) {
+            if let Some(license) = License::from_req(&req.req) {
+                if !licenses.contains(&license) {
+                    licenses.push(license);
+                }
+            }
+        }
+        licenses
+    }
+
+    /// Checks if the package may be used when only the allowed licenses are
+    /// accepted, i.e. if at least one side of each `OR` and both sides of each
+    /// `AND` are allowed
+    ///
+    /// A license with an exception, like `Apache-2.0 WITH LLVM-exception`,
+    /// must be allowed on that same form.
+    ///
+    /// Returns `None` if no allowed licenses were provided. Expressions that
+    /// could not be parsed never satisfy an allow list.
+    #[must_use]
+    pub fn satisfies_allow_list(&self) -> Option<bool> {
+        let allowed = self.allowed.as_ref()?;
+        let Some(expression) = &self.expression else {
+            return Some(false);
+        };
+
+        Some(expression.evaluate(|req| {
+            let id = match &req.license {
+                LicenseItem::Spdx { .. } => License::from_req(req)
+                    .map(|l| l.id().to_string())
+                    .unwrap_or_default(),
+                LicenseItem::Other { lic_ref, .. } => {
+                    format!("LicenseRef-{lic_ref}")
+                }
+            };
+            match &req.exception {
+                Some(e) => allowed.contains(&format!("{id} WITH {}", e.name)),
+                None => allowed.contains(&id),
+            }
+        }))
+    }
+
+    /// The most restrictive category of the licenses in this expression
+    ///
+    /// Licenses not on the SPDX license list are considered permissive, since
+    /// this is used for the license of the root package, which may be
+    /// proprietary.
+    #[must_use]
+    pub fn category(&self) -> LicenseCategory {
+        self.licenses()
+            .iter()
+            .map(License::category)
+            .max()
+            .unwrap_or(LicenseCategory::Permissive)
+    }
+
+    /// Checks if a package with this license can be used by the root package
+    /// set using [`LicenseExpression::with_root_license`], i.e. if there is a
+    /// choice of licenses in the expression compatible with the license of the
+    /// root package
+    ///
+    /// Returns `None` if no root license was set or the expression could not
+    /// be parsed. Licenses not on the SPDX license list are only compatible
+    /// with a root license referring to the same license.
+    #[must_use]
+    pub fn is_compatible_with_root(&self) -> Option<bool> {
+        let root = self.root.as_ref()?;
+        let expression = self.expression.as_ref()?;
+
+        Some(expression.evaluate(|req| match License::from_req(req) {
+            Some(l) => l.is_compatible_with(root),
+            None => {
+                license_ref(req).is_some_and(|r| root.license_refs.contains(&r))
+            }
+        }))
+    }
+}
+
+/// The parts of the license of a root package needed to check if dependencies
+/// are compatible with it
+#[derive(Debug, Clone, Default)]
+struct RootLicense {
+    category: LicenseCategory,
+
+    /// If every choice of licenses in the expression includes `GPL-2.0-only`,
+    /// which is incompatible with some licenses of any category
+    requires_gpl_2_0_only: bool,
+
+    /// References to licenses not on the SPDX license list, like
+    /// `LicenseRef-Proprietary`
+    license_refs: Vec<String>,
+}
+
+impl RootLicense {
+    fn new(expression: &LicenseExpression) -> Self {
+        let requires_gpl_2_0_only =
+            expression.expression.as_ref().is_some_and(|e| {
+                !e.evaluate(|req| {
+                    License::from_req(req)
+                        .is_none_or(|l| l.id() != "GPL-2.0-only")
+                })
+            });
+        let license_refs = expression
+            .expression
+            .iter()
+            .flat_map(Expression::requirements)
+            .filter_map(|r| license_ref(&r.req))
+            .collect();
+
+        Self {
+            category: expression.category(),
+            requires_gpl_2_0_only,
+            license_refs,
+        }
+    }
+}
+
+/// The reference of a license not on the SPDX license list, like
+/// `LicenseRef-Proprietary` or `DocumentRef-spdx-tool-1.2:LicenseRef-MIT-Style-2`
+fn license_ref(req: &LicenseReq) -> Option<String> {
+    match &req.license {
+        LicenseItem::Spdx { .. } => None,
+        LicenseItem::Other { doc_ref, lic_ref } => Some(match doc_ref {
+            Some(d) => format!("DocumentRef-{d}:LicenseRef-{lic_ref}"),
+            None => format!("LicenseRef-{lic_ref}"),
+        }),
+    }
+}
+
+/// How much a license restricts the licensing of works using it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum LicenseCategory {
+    /// Licenses like `MIT` and `Apache-2.0`, that can be used by works under
+    /// any license
+    #[default]
+    Permissive,
+
+    /// Licenses like `MPL-2.0` and `LGPL-3.0-only`, that only require changes
+    /// to the licensed files or library itself to use the same license
+    WeakCopyleft,
+
+    /// Licenses like `GPL-3.0-only`, that require the whole work using it to
+    /// use the same license
+    StrongCopyleft,
+}
+
+impl LicenseCategory {
+    /// Prefixes of copyleft licenses that only apply to the licensed files or
+    /// library
+    const WEAK_COPYLEFT_PREFIXES: [&'static str; 8] = [
+        "LGPL-", "MPL-", "EPL-", "CDDL-", "CPL-", "MS-RL", "OSL-", "EUPL-",
+    ];
+
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LicenseCategory::Permissive => "permissive",
+            LicenseCategory::WeakCopyleft => "weak-copyleft",
+            LicenseCategory::StrongCopyleft => "strong-copyleft",
+        }
+    }
+
+    /// Checks if a dependency with a license of this category can be used by
+    /// a root package with a license of the provided category
+    ///
+    /// Permissive and weak copyleft dependencies can be used by any package,
+    /// but strong copyleft dependencies require the root package to also use
+    /// a strong copyleft license. Some licenses have exceptions to this, which
+    /// [`LicenseExpression::is_compatible_with_root`] takes into account.
+    #[must_use]
+    pub fn is_compatible_with(&self, root: LicenseCategory) -> bool {
+        match self {
+            LicenseCategory::Permissive | LicenseCategory::WeakCopyleft => true,
+            LicenseCategory::StrongCopyleft => {
+                root == LicenseCategory::StrongCopyleft
+            }
+        }
+    }
+}
+
+/// A license on the SPDX license list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct License(LicenseId);
+
+impl License {
+    /// Finds the license a requirement refers to
+    ///
+    /// GNU licenses are represented as their deprecated root license with an
+    /// `or_later` flag when parsed (i.e. `GPL-3.0` for `GPL-3.0-only`), so
+    /// these are converted back to their current identifiers.
+    fn from_req(req: &LicenseReq) -> Option<Self> {
+        let LicenseItem::Spdx { id, or_later } = req.license else {
+            return None;
+        };
+
+        if id.is_gnu() && !id.name.ends_with("-only") {
+            let suffix = if or_later { "or-later" } else { "only" };
+            if let Some(current) =
+                spdx::license_id(&format!("{}-{suffix}", id.name))
+            {
+                return Some(Self(current));
+            }
+        }
+
+        Some(Self(id))
+    }
+
+    /// The SPDX short identifier, like `Apache-2.0`
+    #[must_use]
+    pub fn id(&self) -> &'static str {
+        self.0.name
+    }
+
+    /// The full name, like `Apache License 2.0`
+    #[must_use]
+    pub fn full_name(&self) -> &'static str {
+        self.0.full_name
+    }
+
+    /// If this license is approved by the Open Source Initiative
+    #[must_use]
+    pub fn is_osi_approved(&self) -> bool {
+        self.0.is_osi_approved()
+    }
+
+    /// If this license is considered free/libre by the Free Software
+    /// Foundation
+    #[must_use]
+    pub fn is_fsf_libre(&self) -> bool {
+        self.0.is_fsf_free_libre()
+    }
+
+    /// If this license requires derived works to be licensed under the same
+    /// terms
+    #[must_use]
+    pub fn is_copyleft(&self) -> bool {
+        self.0.is_copyleft()
+    }
+
+    #[must_use]
+    pub fn category(&self) -> LicenseCategory {
+        if !self.0.is_copyleft() {
+            LicenseCategory::Permissive
+        } else if LicenseCategory::WEAK_COPYLEFT_PREFIXES
+            .iter()
+            .any(|p| self.0.name.starts_with(p))
+        {
+            LicenseCategory::WeakCopyleft
+        } else {
+            LicenseCategory::StrongCopyleft
+        }
+    }
+
+    /// Checks if a dependency with this license can be used by a root package
+    /// with the provided license, see [`LicenseCategory::is_compatible_with`]
+    ///
+    /// `GPL-2.0-only` is the exception, as it can not be combined with
+    /// `Apache-2.0` nor version 3 of the GPL and AGPL.
+    fn is_compatible_with(&self, root: &RootLicense) -> bool {
+        const GPL_2_0_ONLY_INCOMPATIBLE: [&str; 5] = [
+            "Apache-2.0",
+            "GPL-3.0-only",
+            "GPL-3.0-or-later",
+            "AGPL-3.0-only",
+            "AGPL-3.0-or-later",
+        ];
+
+        if root.requires_gpl_2_0_only
+            && GPL_2_0_ONLY_INCOMPATIBLE.contains(&self.id())
+        {
+            return false;
+        }
+        self.category().is_compatible_with(root.category)
+    }
+
+    /// If this identifier is deprecated by SPDX, like `GPL-2.0` in favour of
+    /// `GPL-2.0-only`
+    #[must_use]
+    pub fn is_deprecated(&self) -> bool {
+        self.0.is_deprecated()
+    }
+}
+
+/// A file in a package that likely contains license information, like
+/// `LICENSE`, `COPYING` or `NOTICE`
+#[derive(Debug, Clone)]
+pub struct LicenseFile {
+    /// Relative to the directory of the package
+    path: PathBuf,
+
+    /// The best matching license and how similar the file is to it, if any
+    /// license is similar enough
+    detected: Option<(License, f64)>,
+}
+
+impl LicenseFile {
+    /// Finds and classifies the license files in the root of a package
+    /// directory, and the file pointed to by `license-file` in the manifest
+    ///
+    /// Files that cannot be read are skipped.
+    #[must_use]
+    pub fn from_package_dir(
+        package_dir: &Path,
+        license_file: Option<&Path>,
+    ) -> Vec<Self> {
+        let mut paths = fs::read_dir(package_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok().map(|e| e.path()))
+                    .filter(|p| p.is_file() && is_license_file_name(p))
+                    .filter_map(|p| {
+                        p.strip_prefix(package_dir).ok().map(Path::to_path_buf)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        if let Some(l) = license_file {
+            if !paths.iter().any(|p| p == l) {
+                paths.push(l.to_path_buf());
+            }
+        }
+        paths.sort();
+
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let text = fs::read_to_string(package_dir.join(&path)).ok()?;
+                Some(Self {
+                    path,
+                    detected: detect_license(&text),
+                })
+            })
+            .collect()
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[must_use]
+    pub fn detected_license(&self) -> Option<License> {
+        self.detected.map(|(l, _)| l)
+    }
+
+    /// How similar the file is to the text of the detected license, between
+    /// `0.0` and `1.0`
+    #[must_use]
+    pub fn confidence(&self) -> Option<f64> {
+        self.detected.map(|(_, c)| c)
+    }
+}
+
+fn is_license_file_name(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| {
+        let name = n.to_string_lossy().to_uppercase();
+        LICENSE_FILE_PREFIXES.iter().any(|p| name.starts_with(p))
+    })
+}
+
+/// Hashes of all pairs of consecutive words in a text, ignoring case and
+/// punctuation
+fn word_pairs(text: &str) -> HashSet<u64> {
+    let words = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+
+    words
+        .windows(2)
+        .map(|pair| {
+            let mut hasher = DefaultHasher::new();
+            pair.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// Finds the license text most similar to a text, using the
+/// [Sørensen–Dice coefficient](https://en.wikipedia.org/wiki/S%C3%B8rensen%E2%80%93Dice_coefficient)
+/// of their word pairs
+fn detect_license(text: &str) -> Option<(License, f64)> {
+    let pairs = word_pairs(text);
+    if pairs.is_empty() {
+        return None;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    LICENSE_CORPUS
+        .iter()
+        .map(|(id, license_pairs)| {
+            let shared = pairs.intersection(license_pairs).count();
+            let similarity = 2.0 * shared as f64
+                / (pairs.len() + license_pairs.len()) as f64;
+            (License(*id), similarity)
+        })
+        .filter(|(_, similarity)| *similarity >= MIN_CONFIDENCE)
+        // Some licenses share the same text, like `GPL-3.0-only` and
+        // `GPL-3.0-or-later`, in which case the first in the SPDX license list
+        // is used
+        .fold(
+            None,
+            |best: Option<(License, f64)>, (license, similarity)| match best {
+                Some((_, s)) if s >= similarity => best,
+                _ => Some((license, similarity)),
+            },
+        )
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::{detect_license, LicenseCategory, LicenseExpression};
+
+    #[test_case("MIT OR Apache-2.0" => (true, vec!["MIT", "Apache-2.0"]) ; "valid expression")]
+    #[test_case("MIT/Apache-2.0" => (false, vec!["MIT", "Apache-2.0"]) ; "slash as or")]
+    #[test_case("Apache-2.0 AND (MIT OR Apache-2.0)" => (true, vec!["Apache-2.0", "MIT"]) ; "duplicate licenses")]
+    #[test_case("GPL-3.0-only" => (true, vec!["GPL-3.0-only"]) ; "gnu license")]
+    #[test_case("GPL-2.0+" => (false, vec!["GPL-2.0-or-later"]) ; "gnu license with plus")]
+    #[test_case("LicenseRef-Proprietary" => (true, vec![]) ; "license reference")]
+    #[test_case("not a license" => (false, vec![]) ; "invalid expression")]
+    fn parse_licenses(raw: &str) -> (bool, Vec<&'static str>) {
+        let expression = LicenseExpression::new(raw, None);
+        let ids = expression.licenses().iter().map(|l| l.id()).collect();
+        (expression.is_valid_spdx(), ids)
+    }
+
+    #[test_case("MIT OR Apache-2.0", &["MIT"] => Some(true) ; "one side of or allowed")]
+    #[test_case("MIT AND Apache-2.0", &["MIT"] => Some(false) ; "one side of and allowed")]
+    #[test_case("MIT/Apache-2.0", &["Apache-2.0"] => Some(true) ; "lax expression")]
+    #[test_case("GPL-3.0-or-later", &["GPL-3.0-only"] => Some(false) ; "gnu license version")]
+    #[test_case("LicenseRef-Proprietary", &["LicenseRef-Proprietary"] => Some(true) ; "license reference")]
+    #[test_case("not a license", &["MIT"] => Some(false) ; "invalid expression")]
+    #[test_case("Apache-2.0 WITH LLVM-exception", &["Apache-2.0 WITH LLVM-exception"] => Some(true) ; "license with allowed exception")]
+    #[test_case("Apache-2.0 WITH LLVM-exception", &["Apache-2.0"] => Some(false) ; "license without allowed exception")]
+    #[test_case("Apache-2.0", &["Apache-2.0 WITH LLVM-exception"] => Some(false) ; "only license with exception allowed")]
+    fn satisfies_allow_list(raw: &str, allowed: &[&str]) -> Option<bool> {
+        let allowed = allowed.iter().map(ToString::to_string).collect();
+        LicenseExpression::new(raw, Some(allowed)).satisfies_allow_list()
+    }
+
+    #[test_case("MIT" ; "mit")]
+    #[test_case("Apache-2.0" ; "apache")]
+    #[test_case("GPL-3.0-only" ; "gpl")]
+    #[test_case("BSD-3-Clause" ; "bsd")]
+    fn detect_license_text(id: &str) {
+        let text = spdx::license_id(id).unwrap().text();
+        let (license, confidence) = detect_license(text).unwrap();
+        assert_eq!(license.id(), id);
+        assert!(confidence > 0.99);
+    }
+
+    #[test]
+    fn detect_no_license() {
+        assert!(detect_license("This crate is not licensed").is_none());
+    }
+
+    #[test_case("MIT", "GPL-3.0-only" => Some(true) ; "permissive dependency")]
+    #[test_case("MPL-2.0", "MIT" => Some(true) ; "weak copyleft dependency")]
+    #[test_case("GPL-3.0-only", "MIT" => Some(false) ; "strong copyleft dependency with permissive root")]
+    #[test_case("GPL-3.0-only", "GPL-3.0-or-later" => Some(true) ; "strong copyleft dependency with strong copyleft root")]
+    #[test_case("GPL-3.0-only OR MIT", "Apache-2.0" => Some(true) ; "permissive choice")]
+    #[test_case("GPL-3.0-only AND MIT", "Apache-2.0" => Some(false) ; "strong copyleft required")]
+    #[test_case("GPL-3.0-only", "MIT OR GPL-3.0-only" => Some(true) ; "root with copyleft choice")]
+    #[test_case("LicenseRef-Proprietary", "MIT" => Some(false) ; "unknown license")]
+    #[test_case("LicenseRef-Proprietary", "LicenseRef-Proprietary" => Some(true) ; "unknown license same as root")]
+    #[test_case("LicenseRef-Proprietary", "LicenseRef-Other" => Some(false) ; "unknown license other than root")]
+    #[test_case("GPL-3.0-only", "GPL-2.0-only" => Some(false) ; "gpl 3 dependency with gpl 2 only root")]
+    #[test_case("AGPL-3.0-only", "GPL-2.0-only" => Some(false) ; "agpl 3 dependency with gpl 2 only root")]
+    #[test_case("Apache-2.0", "GPL-2.0-only" => Some(false) ; "apache dependency with gpl 2 only root")]
+    #[test_case("Apache-2.0", "GPL-2.0-or-later" => Some(true) ; "apache dependency with gpl 2 or later root")]
+    #[test_case("Apache-2.0", "GPL-2.0-only OR MIT" => Some(true) ; "apache dependency with gpl 2 only choice in root")]
+    #[test_case("MIT", "GPL-2.0-only" => Some(true) ; "mit dependency with gpl 2 only root")]
+    #[test_case("not a license", "MIT" => None ; "invalid expression")]
+    fn compatible_with_root(raw: &str, root: &str) -> Option<bool> {
+        LicenseExpression::new(raw, None)
+            .with_root_license(Some(root))
+            .is_compatible_with_root()
+    }
+
+    #[test_case("MIT" => LicenseCategory::Permissive ; "mit")]
+    #[test_case("MPL-2.0" => LicenseCategory::WeakCopyleft ; "mpl")]
+    #[test_case("LGPL-2.1-only" => LicenseCategory::WeakCopyleft ; "lgpl")]
+    #[test_case("GPL-2.0-only" => LicenseCategory::StrongCopyleft ; "gpl")]
+    #[test_case("AGPL-3.0-only" => LicenseCategory::StrongCopyleft ; "agpl")]
+    #[test_case("MIT AND GPL-2.0-only" => LicenseCategory::StrongCopyleft ; "most restrictive")]
+    fn license_category(raw: &str) -> LicenseCategory {
+        LicenseExpression::new(raw, None).category()
+    }
+
+    #[test]
+    fn compatible_with_unlicensed_root() {
+        let expression = LicenseExpression::new("AGPL-3.0-only", None)
+            .with_root_license(None);
+        assert_eq!(expression.is_compatible_with_root(), Some(false));
+    }
+
+    #[test]
+    fn no_allow_list() {
+        assert_eq!(
+            LicenseExpression::new("MIT", None).satisfies_allow_list(),
+            None
+        );
+    }
+}
@@ -0,0 +1,659 @@
+//! Module to create [`Metadata`] from a `Cargo.lock` file, without running
+//! `cargo metadata`
+//!
+//! A lockfile only contains the names, versions and sources of all packages in
+//! the dependency graph, and what packages they depend on. The rest of the
+//! information is read from the manifests of the packages when they are
+//! available locally, i.e. for workspace members in the same directory as the
+//! lockfile, for registry packages already downloaded to
+//! `~/.cargo/registry/src` and for git dependencies checked out to
+//! `~/.cargo/git/checkouts`.
+//!
+//! Compared to metadata from `cargo metadata`, some information is lost:
+//!
+//! - Packages without a local manifest only have a name, version and source
+//! - Dependencies of packages without a local manifest are all assumed to be
+//!   normal dependencies
+//! - No features are resolved, since the lockfile is the same regardless of
+//!   what features are used
+//! - Dependencies for all platforms are included
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use cargo_metadata::Metadata;
+use cargo_toml::{
+    Dependency, DepsSet, Manifest, OptionalFile, Product, Publish,
+};
+use rustsec::Version;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use walkdir::WalkDir;
+
+use crate::{
+    errors::LockfileError,
+    source::{Source, CRATES_IO_INDEX},
+};
+
+/// The parts of a `Cargo.lock` file used to build the dependency graph
+#[derive(Debug, Clone, Deserialize)]
+struct Lockfile {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: Version,
+    source: Option<String>,
+
+    /// On the form `name`, `name version` or `name version (source)`,
+    /// depending on what is needed to make it unambiguous
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    /// Creates an ID for this package, on the same form as older versions of
+    /// `cargo metadata`
+    fn id(&self, manifest_path: &Path) -> String {
+        match &self.source {
+            Some(s) => format!("{} {} ({s})", self.name, self.version),
+            None => format!(
+                "{} {} (path+file://{})",
+                self.name,
+                self.version,
+                manifest_path.parent().unwrap_or(manifest_path).display()
+            ),
+        }
+    }
+
+    /// Checks if this package is the one referred to by a lockfile dependency
+    /// string
+    fn matches(&self, dependency: &str) -> bool {
+        let mut parts = dependency.splitn(3, ' ');
+        let name = parts.next().unwrap_or_default();
+        let version = parts.next();
+        let source = parts
+            .next()
+            .map(|s| s.trim_start_matches('(').trim_end_matches(')'));
+
+        name == self.name
+            && version.is_none_or(|v| v == self.version.to_string())
+            && source.is_none_or(|s| self.source.as_deref() == Some(s))
+    }
+}
+
+/// Retrieves the default `CARGO_HOME` directory, i.e. `$CARGO_HOME` if set or
+/// `~/.cargo` otherwise
+#[must_use]
+pub fn default_cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cargo"))
+        })
+}
+
+/// Creates [`Metadata`] from a `Cargo.lock` file, reading manifests of the
+/// workspace and of packages downloaded to `cargo_home` when available
+///
+/// The directory of the lockfile is assumed to be the workspace root.
+///
+/// # Errors
+///
+/// Returns an error variant if the lockfile cannot be read or parsed, or if it
+/// refers to packages it does not contain.
+pub fn metadata_from_lockfile(
+    lockfile_path: &Path,
+    cargo_home: Option<&Path>,
+) -> Result<Metadata, Box<LockfileError>> {
+    let lockfile_path_string = lockfile_path.to_string_lossy().to_string();
+    let lockfile_content = fs::read_to_string(lockfile_path).map_err(|e| {
+        LockfileError::CouldNotRead(lockfile_path_string.clone(), e.to_string())
+    })?;
+    let lockfile =
+        toml::from_str::<Lockfile>(&lockfile_content).map_err(|e| {
+            LockfileError::CouldNotParse(
+                lockfile_path_string.clone(),
+                e.to_string(),
+            )
+        })?;
+
+    let workspace_root = fs::canonicalize(lockfile_path)
+        .ok()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("."));
+    let workspace_manifests = workspace_manifests(&workspace_root);
+    let registry_src_dirs = cargo_home
+        .map(|h| registry_src_dirs(&h.join("registry").join("src")))
+        .unwrap_or_default();
+    let git_checkouts = cargo_home.map(|h| h.join("git").join("checkouts"));
+
+    // Packages not downloaded are given a manifest path where they would be,
+    // had they been downloaded
+    let fallback_cargo_home = cargo_home
+        .map(Path::to_path_buf)
+        .or_else(default_cargo_home)
+        .unwrap_or_default();
+
+    // Find the manifest of each package, or where it would be if available
+    let manifest_paths = lockfile
+        .package
+        .iter()
+        .map(|p| match &p.source {
+            None => workspace_manifests
+                .get(&(p.name.clone(), p.version.clone()))
+                .cloned()
+                .unwrap_or_else(|| {
+                    workspace_root.join(&p.name).join("Cargo.toml")
+                }),
+            Some(s) if s.starts_with("git+") => git_checkouts
+                .as_deref()
+                .and_then(|c| git_checkout_manifest(c, p))
+                .unwrap_or_else(|| {
+                    fallback_cargo_home
+                        .join("git")
+                        .join("checkouts")
+                        .join(&p.name)
+                        .join("Cargo.toml")
+                }),
+            Some(_) => {
+                let dir_name = format!("{}-{}", p.name, p.version);
+                registry_src_dirs
+                    .iter()
+                    .map(|d| d.join(&dir_name).join("Cargo.toml"))
+                    .find(|p| p.exists())
+                    .unwrap_or_else(|| {
+                        fallback_cargo_home
+                            .join("registry")
+                            .join("src")
+                            .join(&dir_name)
+                            .join("Cargo.toml")
+                    })
+            }
+        })
+        .collect::<Vec<_>>();
+    let ids = lockfile
+        .package
+        .iter()
+        .zip(&manifest_paths)
+        .map(|(p, m)| p.id(m))
+        .collect::<Vec<_>>();
+
+    // Dependencies are referred to by name, and only by version and source
+    // if that is ambiguous
+    let mut indices_by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, p) in lockfile.package.iter().enumerate() {
+        indices_by_name.entry(&p.name).or_default().push(i);
+    }
+
+    let mut packages = Vec::with_capacity(lockfile.package.len());
+    let mut nodes = Vec::with_capacity(lockfile.package.len());
+    let mut workspace_members = Vec::new();
+
+    for (i, locked) in lockfile.package.iter().enumerate() {
+        let manifest_path = &manifest_paths[i];
+        let manifest = if manifest_path.exists() {
+            Manifest::from_path(manifest_path).ok()
+        } else {
+            None
+        };
+
+        let mut dependencies = Vec::with_capacity(locked.dependencies.len());
+        let mut deps = Vec::with_capacity(locked.dependencies.len());
+        for dependency in &locked.dependencies {
+            let name = dependency.split(' ').next().unwrap_or_default();
+            let j = indices_by_name
+                .get(name)
+                .and_then(|is| {
+                    is.iter()
+                        .copied()
+                        .find(|&j| lockfile.package[j].matches(dependency))
+                })
+                .ok_or_else(|| {
+                    LockfileError::UnknownDependency(
+                        dependency.clone(),
+                        locked.name.clone(),
+                    )
+                })?;
+            let dependency_name = &lockfile.package[j].name;
+            dependencies.push(ids[j].clone());
+            deps.push(node_dep(manifest.as_ref(), dependency_name, &ids[j]));
+        }
+
+        nodes.push(json!({
+            "id": ids[i],
+            "dependencies": dependencies,
+            "deps": deps,
+            "features": [],
+        }));
+
+        if locked.source.is_none() && manifest.is_some() {
+            workspace_members.push(ids[i].clone());
+        }
+
+        packages.push(package(locked, &ids[i], manifest_path, manifest));
+    }
+
+    let root_manifest_path = workspace_root.join("Cargo.toml");
+    let root = manifest_paths
+        .iter()
+        .position(|m| *m == root_manifest_path)
+        .filter(|i| lockfile.package[*i].source.is_none())
+        .map(|i| ids[i].clone());
+
+    let metadata = json!({
+        "packages": packages,
+        "workspace_members": workspace_members,
+        "resolve": {
+            "nodes": nodes,
+            "root": root,
+        },
+        "workspace_root": workspace_root,
+        "target_directory": workspace_root.join("target"),
+        "version": 1,
+    });
+
+    serde_json::from_value(metadata).map_err(|e| {
+        Box::new(LockfileError::CouldNotParse(
+            lockfile_path_string,
+            e.to_string(),
+        ))
+    })
+}
+
+/// Finds all package manifests in a workspace, by name and version
+fn workspace_manifests(
+    workspace_root: &Path,
+) -> HashMap<(String, Version), PathBuf> {
+    WalkDir::new(workspace_root)
+        .follow_links(true)
+        .into_iter()
+        .filter_entry(|e| {
+            // Skip build output and hidden directories, like `.git`
+            e.depth() == 0
+                || !(e.file_name() == "target"
+                    || e.file_name().to_string_lossy().starts_with('.'))
+        })
+        .filter_map(|entry| match entry {
+            Ok(e) if e.file_name() == "Cargo.toml" => {
+                let path = e.into_path();
+                let manifest = Manifest::from_path(&path).ok()?;
+                let package = manifest.package?;
+                let version = Version::parse(package.version()).ok()?;
+                Some(((package.name, version), path))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the manifest of a package from a git repository, checked out to
+/// `<git_checkouts>/<repository name>-<hash>/<short commit>`
+fn git_checkout_manifest(
+    git_checkouts: &Path,
+    package: &LockedPackage,
+) -> Option<PathBuf> {
+    let source = Source::parse(package.source.as_deref()?);
+    let commit = source.locked_commit()?;
+    let repository_name = source
+        .url()?
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()?
+        .trim_end_matches(".git");
+
+    let entries = |dir: &Path| {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_dir())
+            .collect::<Vec<_>>()
+    };
+    let file_name = |path: &Path| {
+        path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    entries(git_checkouts)
+        .into_iter()
+        .filter(|repository| {
+            file_name(repository)
+                .rsplit_once('-')
+                .is_some_and(|(name, _)| name == repository_name)
+        })
+        .flat_map(|repository| entries(&repository))
+        .filter(|checkout| commit.starts_with(&file_name(checkout)))
+        .find_map(|checkout| {
+            // A repository may contain more than one package
+            workspace_manifests(&checkout)
+                .remove(&(package.name.clone(), package.version.clone()))
+        })
+}
+
+/// Lists the directories packages of each registry are extracted to
+fn registry_src_dirs(registry_src: &Path) -> Vec<PathBuf> {
+    let mut dirs = fs::read_dir(registry_src)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_dir())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+/// All dependency declarations in a manifest, as
+/// `(kind, target, name, dependency)`
+fn declared_dependencies(
+    manifest: &Manifest,
+) -> Vec<(Option<&'static str>, Option<&str>, &str, &Dependency)> {
+    fn with_kind<'a>(
+        deps: &'a DepsSet,
+        kind: Option<&'static str>,
+        target: Option<&'a str>,
+    ) -> impl Iterator<
+        Item = (
+            Option<&'static str>,
+            Option<&'a str>,
+            &'a str,
+            &'a Dependency,
+        ),
+    > {
+        deps.iter().map(move |(n, d)| (kind, target, n.as_str(), d))
+    }
+
+    let mut declared = Vec::new();
+    declared.extend(with_kind(&manifest.dependencies, None, None));
+    declared.extend(with_kind(&manifest.dev_dependencies, Some("dev"), None));
+    declared.extend(with_kind(
+        &manifest.build_dependencies,
+        Some("build"),
+        None,
+    ));
+    for (target, t) in &manifest.target {
+        let target = Some(target.as_str());
+        declared.extend(with_kind(&t.dependencies, None, target));
+        declared.extend(with_kind(&t.dev_dependencies, Some("dev"), target));
+        declared.extend(with_kind(
+            &t.build_dependencies,
+            Some("build"),
+            target,
+        ));
+    }
+    declared
+}
+
+/// Creates a resolved dependency on the package `dependency_name`, using the
+/// declarations in the manifest of the dependent package to find out how it
+/// is used
+fn node_dep(
+    manifest: Option<&Manifest>,
+    dependency_name: &str,
+    dependency_id: &str,
+) -> Value {
+    let declarations = manifest
+        .map(|m| {
+            declared_dependencies(m)
+                .into_iter()
+                .filter(|(_, _, name, d)| {
+                    d.package().unwrap_or(name) == dependency_name
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    // The name used in code, which is affected by renames
+    let name = declarations
+        .first()
+        .map_or(dependency_name, |(_, _, name, _)| name)
+        .replace('-', "_");
+    let dep_kinds = if declarations.is_empty() {
+        vec![json!({ "kind": null, "target": null })]
+    } else {
+        declarations
+            .iter()
+            .map(|(kind, target, _, _)| json!({ "kind": kind, "target": target }))
+            .collect()
+    };
+
+    json!({
+        "name": name,
+        "pkg": dependency_id,
+        "dep_kinds": dep_kinds,
+    })
+}
+
+/// Creates a package as it is represented by `cargo metadata`
+fn package(
+    locked: &LockedPackage,
+    id: &str,
+    manifest_path: &Path,
+    manifest: Option<Manifest>,
+) -> Value {
+    let mut package = json!({
+        "name": locked.name,
+        "version": locked.version.to_string(),
+        "id": id,
+        "source": locked.source,
+        "description": null,
+        "dependencies": [],
+        "license": null,
+        "license_file": null,
+        "targets": [],
+        "features": {},
+        "manifest_path": manifest_path,
+        "readme": null,
+        "repository": null,
+        "homepage": null,
+        "documentation": null,
+        "links": null,
+        "publish": null,
+        "default_run": null,
+        "rust_version": null,
+    });
+
+    let Some(manifest) = manifest else {
+        return package;
+    };
+    let Some(p) = &manifest.package else {
+        return package;
+    };
+    let dir = manifest_path.parent().unwrap_or(manifest_path);
+
+    package["authors"] = json!(p.authors());
+    package["description"] = json!(p.description());
+    package["license"] = json!(p.license());
+    package["license_file"] = json!(p.license_file());
+    package["categories"] = json!(p.categories());
+    package["keywords"] = json!(p.keywords());
+    package["readme"] = match p.readme() {
+        OptionalFile::Path(r) => json!(r),
+        OptionalFile::Flag(_) => Value::Null,
+    };
+    package["repository"] = json!(p.repository());
+    package["homepage"] = json!(p.homepage());
+    package["documentation"] = json!(p.documentation());
+    package["edition"] = json!(p.edition());
+    package["links"] = json!(p.links());
+    package["publish"] = match p.publish() {
+        Publish::Flag(true) => Value::Null,
+        Publish::Flag(false) => json!([]),
+        Publish::Registry(r) => json!(r),
+    };
+    package["default_run"] = json!(p.default_run);
+    package["rust_version"] = json!(p.rust_version());
+    package["dependencies"] = declared_dependencies(&manifest)
+        .into_iter()
+        .map(|(kind, target, name, d)| dependency(kind, target, name, d))
+        .collect();
+    package["features"] = json!(features(&manifest));
+    package["targets"] = json!(targets(&manifest, dir));
+
+    package
+}
+
+/// Creates a dependency declaration as it is represented by `cargo metadata`
+fn dependency(
+    kind: Option<&str>,
+    target: Option<&str>,
+    name: &str,
+    dependency: &Dependency,
+) -> Value {
+    let detail = dependency.detail();
+    let source = match detail {
+        Some(d) if d.path.is_some() => None,
+        Some(d) if d.git.is_some() => {
+            d.git.as_ref().map(|g| format!("git+{g}"))
+        }
+        Some(d) if d.registry.is_some() || d.registry_index.is_some() => {
+            d.registry_index.as_ref().map(|r| format!("registry+{r}"))
+        }
+        _ => Some(format!("registry+{CRATES_IO_INDEX}")),
+    };
+    let (package_name, rename) = match dependency.package() {
+        Some(p) => (p, Some(name)),
+        None => (name, None),
+    };
+
+    json!({
+        "name": package_name,
+        "source": source,
+        "req": dependency.req(),
+        "kind": kind,
+        "optional": dependency.optional(),
+        "uses_default_features": detail.is_none_or(|d| d.default_features),
+        "features": dependency.req_features(),
+        "target": target,
+        "rename": rename,
+        "registry": detail.and_then(|d| d.registry.as_deref()),
+        "path": detail.and_then(|d| d.path.as_deref()),
+    })
+}
+
+/// The features of a package, including the implicit features of optional
+/// dependencies not referred to using `dep:`
+fn features(manifest: &Manifest) -> BTreeMap<String, Vec<String>> {
+    let mut features = manifest.features.clone();
+    for (_, _, name, d) in declared_dependencies(manifest) {
+        let dep_feature = format!("dep:{name}");
+        let referred = manifest
+            .features
+            .values()
+            .flatten()
+            .any(|v| *v == dep_feature);
+        if d.optional() && !referred {
+            features
+                .entry(name.to_string())
+                .or_insert_with(|| vec![dep_feature]);
+        }
+    }
+    features
+}
+
+/// The targets of a package, as they are discovered by [`Manifest`]
+fn targets(manifest: &Manifest, dir: &Path) -> Vec<Value> {
+    let package_name = manifest
+        .package
+        .as_ref()
+        .map(|p| p.name().replace('-', "_"))
+        .unwrap_or_default();
+    let target = |product: &Product,
+                  kind: Vec<String>,
+                  crate_types,
+                  default| {
+        json!({
+            "name": product.name.clone().unwrap_or_else(|| package_name.clone()),
+            "kind": kind,
+            "crate_types": crate_types,
+            "required-features": product.required_features,
+            "src_path": dir.join(product.path.as_deref().unwrap_or(default)),
+            "edition": product.edition,
+            "doctest": product.doctest,
+            "test": product.test,
+            "doc": product.doc,
+        })
+    };
+
+    let mut targets = Vec::new();
+    if let Some(lib) = &manifest.lib {
+        let kind = if lib.proc_macro {
+            vec![String::from("proc-macro")]
+        } else if lib.crate_type.is_empty() {
+            vec![String::from("lib")]
+        } else {
+            lib.crate_type.clone()
+        };
+        targets.push(target(lib, kind.clone(), kind, "src/lib.rs"));
+    }
+    for (products, kind) in [
+        (&manifest.bin, "bin"),
+        (&manifest.example, "example"),
+        (&manifest.test, "test"),
+        (&manifest.bench, "bench"),
+    ] {
+        for product in products {
+            targets.push(target(
+                product,
+                vec![String::from(kind)],
+                vec![String::from("bin")],
+                "src/main.rs",
+            ));
+        }
+    }
+
+    let build_script =
+        match manifest.package.as_ref().and_then(|p| p.build.as_ref()) {
+            Some(OptionalFile::Path(p)) => Some(dir.join(p)),
+            Some(OptionalFile::Flag(false)) => None,
+            Some(OptionalFile::Flag(true)) | None => {
+                Some(dir.join("build.rs")).filter(|p| p.exists())
+            }
+        };
+    if let Some(src_path) = build_script {
+        targets.push(json!({
+            "name": "build-script-build",
+            "kind": ["custom-build"],
+            "crate_types": ["bin"],
+            "src_path": src_path,
+            "edition": manifest.package.as_ref().map(|p| p.edition()),
+        }));
+    }
+
+    targets
+}
+
+#[cfg(test)]
+mod test {
+    use rustsec::Version;
+    use test_case::test_case;
+
+    use super::LockedPackage;
+
+    #[test_case("syn" => true ; "name only")]
+    #[test_case("syn 2.0.0" => true ; "name and version")]
+    #[test_case("syn 2.0.0 (registry+https://github.com/rust-lang/crates.io-index)" => true ; "name version and source")]
+    #[test_case("syn 1.0.107" => false ; "other version")]
+    #[test_case("syn 2.0.0 (git+https://github.com/dtolnay/syn#abc)" => false ; "other source")]
+    #[test_case("quote" => false ; "other name")]
+    fn matches_dependency(dependency: &str) -> bool {
+        let package = LockedPackage {
+            name: String::from("syn"),
+            version: Version::new(2, 0, 0),
+            source: Some(String::from(
+                "registry+https://github.com/rust-lang/crates.io-index",
+            )),
+            dependencies: Vec::new(),
+        };
+        package.matches(dependency)
+    }
+}
@@ -108,7 +108,7 @@ impl ManifestPath {
         // equal to what we're looking for
         if ctf
             .package
-            .map_or(true, |p| !Self::equal_package_names(p.name(), name))
+            .is_none_or(|p| !Self::equal_package_names(p.name(), name))
         {
             // It is probably a workspace, we'll have to find a `Cargo.toml`
             // file with matching name
@@ -152,6 +152,12 @@ impl ManifestPath {
         }
     }
 
+    /// Creates a path to a `Cargo.toml` manifest that may not exist, such as
+    /// when only a lockfile is available
+    pub(crate) fn unchecked(path: PathBuf) -> Self {
+        Self(path)
+    }
+
     #[must_use]
     pub fn as_path(&self) -> &Path {
         &self.0
@@ -171,6 +177,35 @@ impl ManifestPath {
         &self,
         features: Vec<CargoOpt>,
     ) -> Result<Metadata, Box<dyn Error>> {
+        let res = self.metadata_command(features).exec()?;
+        Ok(res)
+    }
+
+    /// Extracts metadata from a `Cargo.toml` file like
+    /// [`ManifestPath::metadata`], but only resolves dependencies used when
+    /// compiling for a target triple, like `x86_64-unknown-linux-gnu`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error variant if the metadata command fails, such as if
+    /// the features provided are not of a possible combination, or the target
+    /// is not known to `rustc`.
+    pub fn metadata_for_target(
+        &self,
+        features: Vec<CargoOpt>,
+        target_platform: &str,
+    ) -> Result<Metadata, Box<dyn Error>> {
+        let res = self
+            .metadata_command(features)
+            .other_options(vec![
+                String::from("--filter-platform"),
+                target_platform.to_string(),
+            ])
+            .exec()?;
+        Ok(res)
+    }
+
+    fn metadata_command(&self, features: Vec<CargoOpt>) -> MetadataCommand {
         let mut m = MetadataCommand::new();
         m.manifest_path(self.as_path());
 
@@ -178,8 +213,7 @@ impl ManifestPath {
             m.features(feature);
         }
 
-        let res = m.exec()?;
-        Ok(res)
+        m
     }
 }
 
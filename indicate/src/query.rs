@@ -93,7 +93,7 @@ impl FullQueryBuilder {
     pub fn build(self) -> FullQuery {
         FullQuery {
             query: self.query,
-            args: self.args.unwrap_or(BTreeMap::new()),
+            args: self.args.unwrap_or_default(),
         }
     }
 }
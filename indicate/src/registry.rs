@@ -0,0 +1,503 @@
+//! Configuration and clients for alternative registries, i.e. registries other
+//! than crates.io that implement the same web API
+//!
+//! Registries are identified by the URL of their index, which is how
+//! `cargo metadata` refers to them in the source of a package. They can be
+//! configured explicitly using [`RegistryConfig`], or read from the
+//! `[registries]` table of Cargo configuration files using
+//! [`registries_from_cargo_config`].
+//!
+//! See [the Cargo book](https://doc.rust-lang.org/cargo/reference/registries.html)
+//! for more information about alternative registries.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crates_io_api::{CrateDownloads, CrateResponse, User};
+use once_cell::unsync::OnceCell;
+use serde::{de::DeserializeOwned, Deserialize};
+use url::Url;
+
+use crate::{
+    crates_io::CratesIoClient, errors::RegistryError,
+    registry_index::RegistryIndexClient, NameVersion, RUNTIME,
+};
+
+/// Configuration of an alternative registry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryConfig {
+    /// The URL of the index, like `sparse+https://my-registry.com/index/`
+    pub(crate) index_url: String,
+
+    /// The base URL of the web API, like `https://my-registry.com`
+    ///
+    /// If not set, it is read from the `config.json` of the index.
+    pub(crate) api_url: Option<String>,
+    pub(crate) token: Option<String>,
+
+    /// A local copy of the index, see [`RegistryIndexClient::new`]
+    pub(crate) index_path: Option<PathBuf>,
+}
+
+impl RegistryConfig {
+    /// Creates a configuration for the registry with this index URL, with or
+    /// without the `sparse+` prefix used by Cargo
+    #[must_use]
+    pub fn new(index_url: &str) -> Self {
+        Self {
+            index_url: index_url.to_string(),
+            api_url: None,
+            token: None,
+            index_path: None,
+        }
+    }
+
+    /// The base URL of the web API of the registry, like
+    /// `https://my-registry.com`, to which `/api/v1/crates` is appended
+    #[must_use]
+    pub fn api_url(mut self, api_url: String) -> Self {
+        self.api_url = Some(api_url);
+        self
+    }
+
+    /// Token used to authenticate to the web API of the registry
+    #[must_use]
+    pub fn token(mut self, token: String) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// A local copy of the index, used for versions and their yank status
+    #[must_use]
+    pub fn index_path(mut self, index_path: PathBuf) -> Self {
+        self.index_path = Some(index_path);
+        self
+    }
+
+    /// Identifies the registry, regardless of the protocol of the index URL
+    #[must_use]
+    pub fn key(&self) -> String {
+        registry_key(&self.index_url)
+    }
+
+    /// Fills in what is not set in this configuration from another
+    /// configuration of the same registry
+    fn or(self, other: RegistryConfig) -> Self {
+        Self {
+            index_url: self.index_url,
+            api_url: self.api_url.or(other.api_url),
+            token: self.token.or(other.token),
+            index_path: self.index_path.or(other.index_path),
+        }
+    }
+
+    /// Finds the base URL of the web API, reading the `config.json` of the
+    /// index if it is not set explicitly
+    ///
+    /// The `config.json` is only fetched for sparse indices, since `git`
+    /// indices would have to be cloned.
+    pub(crate) fn resolve_api_url(
+        &self,
+    ) -> Result<Option<String>, Box<RegistryError>> {
+        if let Some(api_url) = &self.api_url {
+            return Ok(Some(api_url.clone()));
+        }
+
+        let index_config: IndexConfig = if let Some(p) = &self.index_path {
+            let path = p.join("config.json");
+            let raw = fs::read_to_string(&path).map_err(|e| {
+                RegistryError::CouldNotRead(
+                    path.to_string_lossy().to_string(),
+                    e.to_string(),
+                )
+            })?;
+            serde_json::from_str(&raw).map_err(|e| {
+                RegistryError::CouldNotParse(
+                    path.to_string_lossy().to_string(),
+                    e.to_string(),
+                )
+            })?
+        } else if let Some(sparse) = self.index_url.strip_prefix("sparse+") {
+            let url = format!("{}/config.json", sparse.trim_end_matches('/'));
+            get(&reqwest::Client::new(), &url, self.token.as_deref())?
+        } else {
+            return Ok(None);
+        };
+
+        Ok(index_config.api)
+    }
+}
+
+/// The parts of the `config.json` of an index that are used
+#[derive(Debug, Deserialize)]
+struct IndexConfig {
+    api: Option<String>,
+}
+
+/// Identifies a registry by the URL of its index, without the protocol prefix
+/// used by Cargo and any trailing slash
+pub(crate) fn registry_key(index_url: &str) -> String {
+    index_url
+        .trim_start_matches("sparse+")
+        .trim_start_matches("registry+")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// A `.cargo/config.toml` file, ignoring everything but registries
+#[derive(Debug, Default, Deserialize)]
+struct CargoConfig {
+    #[serde(default)]
+    registries: BTreeMap<String, CargoConfigRegistry>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+struct CargoConfigRegistry {
+    index: Option<String>,
+    token: Option<String>,
+}
+
+/// Reads the alternative registries configured in the `[registries]` table of
+/// Cargo configuration files that apply to `dir`
+///
+/// Like Cargo, `.cargo/config.toml` (or `.cargo/config`) files in `dir` and
+/// its parents are read, followed by `config.toml` in `cargo_home`. Values in
+/// files closer to `dir` take precedence. Registries without an index URL,
+/// such as when only setting a token for a registry, are not included.
+///
+/// # Errors
+///
+/// Returns an error variant if a configuration file exists but cannot be read
+/// or parsed.
+pub fn registries_from_cargo_config(
+    dir: &Path,
+    cargo_home: Option<&Path>,
+) -> Result<Vec<RegistryConfig>, Box<RegistryError>> {
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    let config_dirs = dir
+        .ancestors()
+        .map(|d| d.join(".cargo"))
+        .chain(cargo_home.map(Path::to_path_buf));
+
+    let mut registries: BTreeMap<String, CargoConfigRegistry> = BTreeMap::new();
+    for config_dir in config_dirs {
+        let Some(path) = ["config.toml", "config"]
+            .iter()
+            .map(|f| config_dir.join(f))
+            .find(|p| p.is_file())
+        else {
+            continue;
+        };
+
+        let raw = fs::read_to_string(&path).map_err(|e| {
+            RegistryError::CouldNotRead(
+                path.to_string_lossy().to_string(),
+                e.to_string(),
+            )
+        })?;
+        let config: CargoConfig = toml::from_str(&raw).map_err(|e| {
+            RegistryError::CouldNotParse(
+                path.to_string_lossy().to_string(),
+                e.to_string(),
+            )
+        })?;
+
+        // Files read earlier are closer, and take precedence
+        for (name, r) in config.registries {
+            let entry = registries.entry(name).or_default();
+            entry.index = entry.index.take().or(r.index);
+            entry.token = entry.token.take().or(r.token);
+        }
+    }
+
+    Ok(registries
+        .into_values()
+        .filter_map(|r| {
+            let config = RegistryConfig::new(&r.index?);
+            Some(match r.token {
+                Some(t) => config.token(t),
+                None => config,
+            })
+        })
+        .collect())
+}
+
+/// Client for the web API of an alternative registry, implementing the same
+/// endpoints as crates.io
+pub(crate) struct RegistryApiClient {
+    client: reqwest::Client,
+    api_url: String,
+    token: Option<String>,
+}
+
+impl RegistryApiClient {
+    pub(crate) fn new(api_url: &str, token: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_url: api_url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    pub(crate) fn get_crate(
+        &self,
+        crate_name: &str,
+    ) -> Result<CrateResponse, Box<RegistryError>> {
+        self.get(&format!("api/v1/crates/{crate_name}"))
+    }
+
+    pub(crate) fn crate_owners(
+        &self,
+        crate_name: &str,
+    ) -> Result<Vec<User>, Box<RegistryError>> {
+        #[derive(Deserialize)]
+        struct Owners {
+            users: Vec<User>,
+        }
+
+        self.get::<Owners>(&format!("api/v1/crates/{crate_name}/owners"))
+            .map(|o| o.users)
+    }
+
+    pub(crate) fn crate_downloads(
+        &self,
+        crate_name: &str,
+    ) -> Result<CrateDownloads, Box<RegistryError>> {
+        self.get(&format!("api/v1/crates/{crate_name}/downloads"))
+    }
+
+    pub(crate) fn reverse_dependencies_count(
+        &self,
+        crate_name: &str,
+    ) -> Result<u64, Box<RegistryError>> {
+        #[derive(Deserialize)]
+        struct ReverseDependencies {
+            meta: Meta,
+        }
+
+        #[derive(Deserialize)]
+        struct Meta {
+            total: u64,
+        }
+
+        self.get::<ReverseDependencies>(&format!(
+            "api/v1/crates/{crate_name}/reverse_dependencies?per_page=1"
+        ))
+        .map(|r| r.meta.total)
+    }
+
+    fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+    ) -> Result<T, Box<RegistryError>> {
+        get(
+            &self.client,
+            &format!("{}/{path}", self.api_url),
+            self.token.as_deref(),
+        )
+    }
+}
+
+/// Makes a `GET` request, passing the token as is in the `Authorization`
+/// header like Cargo does
+fn get<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+) -> Result<T, Box<RegistryError>> {
+    let url = Url::parse(url).map_err(|e| {
+        RegistryError::RequestFailed(url.to_string(), e.to_string())
+    })?;
+    let mut request = client.get(url.clone());
+    if let Some(t) = token {
+        request = request.header(reqwest::header::AUTHORIZATION, t);
+    }
+
+    let raw = RUNTIME
+        .block_on(async {
+            request.send().await?.error_for_status()?.text().await
+        })
+        .map_err(|e| {
+            RegistryError::RequestFailed(url.to_string(), e.to_string())
+        })?;
+    serde_json::from_str(&raw).map_err(|e| {
+        Box::new(RegistryError::CouldNotParse(url.to_string(), e.to_string()))
+    })
+}
+
+/// A package from crates.io or an alternative registry
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegistryPackage {
+    pub name_version: NameVersion,
+
+    /// The key of the registry, see [`RegistryConfig::key`], or `None` for
+    /// crates.io
+    pub registry: Option<String>,
+
+    /// If the package comes from the registry; Nothing is known about
+    /// packages that do not, such as path packages named like a crate
+    pub is_from_registry: bool,
+}
+
+/// Clients for crates.io and all configured alternative registries, created
+/// when first used
+pub(crate) struct RegistryClients {
+    crates_io: OnceCell<Rc<RefCell<CratesIoClient>>>,
+
+    /// The path to a database dump, and the names of the crates to read from
+    /// it
+    crates_io_db_dump: Option<(PathBuf, HashSet<String>)>,
+    crates_io_index: Option<RegistryIndexClient>,
+    configs: HashMap<String, RegistryConfig>,
+    clients: RefCell<HashMap<String, Rc<RefCell<CratesIoClient>>>>,
+
+    /// Used for packages not from a registry, answering nothing
+    unknown: OnceCell<Rc<RefCell<CratesIoClient>>>,
+}
+
+impl RegistryClients {
+    /// Creates clients using the configuration of the adapter builder,
+    /// where explicitly configured registries take precedence over those
+    /// from Cargo configuration files
+    pub(crate) fn new(
+        crates_io_client: Option<CratesIoClient>,
+        crates_io_db_dump: Option<(PathBuf, HashSet<String>)>,
+        crates_io_index: Option<RegistryIndexClient>,
+        configs: Vec<RegistryConfig>,
+    ) -> Self {
+        let mut by_key: HashMap<String, RegistryConfig> = HashMap::new();
+        for config in configs {
+            let config = match by_key.remove(&config.key()) {
+                Some(existing) => existing.or(config),
+                None => config,
+            };
+            by_key.insert(config.key(), config);
+        }
+
+        Self {
+            crates_io: crates_io_client.map_or_else(OnceCell::default, |c| {
+                OnceCell::with_value(Rc::new(RefCell::new(c)))
+            }),
+            crates_io_db_dump,
+            crates_io_index,
+            configs: by_key,
+            clients: RefCell::default(),
+            unknown: OnceCell::default(),
+        }
+    }
+
+    /// Retrieves or creates the client for crates.io
+    ///
+    /// If a database dump has been set, it is read here, since doing so is
+    /// expensive. A registry index is used before the dump or API if one has
+    /// been set.
+    pub(crate) fn crates_io(&self) -> Rc<RefCell<CratesIoClient>> {
+        let c = self.crates_io.get_or_init(|| {
+            let client = if let Some((p, names)) = &self.crates_io_db_dump {
+                CratesIoClient::from_db_dump(p, Some(names)).unwrap_or_else(
+                    |e| {
+                        panic!(
+                            "could not create CratesIoClient due to error: {e}"
+                        )
+                    },
+                )
+            } else if self.crates_io_index.is_some()
+                && std::env::var_os("USER_AGENT").is_none()
+            {
+                // Allow queries only using index data to run without a user
+                // agent for the API
+                CratesIoClient::offline()
+            } else {
+                CratesIoClient::default()
+            };
+            let client = match &self.crates_io_index {
+                Some(i) => client.registry_index(i.clone()),
+                None => client,
+            };
+            Rc::new(RefCell::new(client))
+        });
+        Rc::clone(c)
+    }
+
+    /// Checks if a registry has been configured, see [`RegistryConfig::key`]
+    pub(crate) fn is_configured(&self, registry: &str) -> bool {
+        self.configs.contains_key(registry)
+    }
+
+    /// Runs `f` with the client for the registry a package comes from, and
+    /// the name and version of the package
+    ///
+    /// Packages not from a registry get a client without a backend or index,
+    /// see [`RegistryPackage::is_from_registry`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the registry of the package has not been configured.
+    pub(crate) fn with_client<T>(
+        &self,
+        package: &RegistryPackage,
+        f: impl FnOnce(&mut CratesIoClient, &NameVersion) -> T,
+    ) -> T {
+        let client = match &package.registry {
+            _ if !package.is_from_registry => {
+                Rc::clone(self.unknown.get_or_init(|| {
+                    Rc::new(RefCell::new(CratesIoClient::offline()))
+                }))
+            }
+            Some(registry) => Rc::clone(
+                self.clients
+                    .borrow_mut()
+                    .entry(registry.clone())
+                    .or_insert_with(|| {
+                        let config =
+                            self.configs.get(registry).unwrap_or_else(|| {
+                                panic!("unknown registry: {registry}")
+                            });
+                        Rc::new(RefCell::new(CratesIoClient::from_registry(
+                            config,
+                        )))
+                    }),
+            ),
+            None => self.crates_io(),
+        };
+        let mut client = client.borrow_mut();
+        f(&mut client, &package.name_version)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use test_case::test_case;
+
+    use super::{registries_from_cargo_config, registry_key, RegistryConfig};
+
+    #[test_case("sparse+https://my-registry.com/index/" ; "sparse")]
+    #[test_case("registry+https://my-registry.com/index" ; "git from package source")]
+    #[test_case("https://my-registry.com/index" ; "git")]
+    fn key(index_url: &str) {
+        assert_eq!(registry_key(index_url), "https://my-registry.com/index");
+    }
+
+    #[test]
+    fn read_cargo_config() {
+        let registries = registries_from_cargo_config(
+            Path::new("test_data/fake_crates/alt_registry/member"),
+            Some(Path::new("test_data/fake_cargo_home")),
+        )
+        .unwrap();
+        assert_eq!(
+            registries,
+            vec![RegistryConfig::new(
+                "sparse+https://my-registry.example.com/index/"
+            )
+            .token(String::from("member-token"))]
+        );
+    }
+}
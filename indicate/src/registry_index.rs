@@ -0,0 +1,263 @@
+//! Client for reading the versions of crates from a registry index, without
+//! using the web API of the registry
+//!
+//! An index contains one file per crate, with one line of JSON per published
+//! version. Three ways of storing an index locally are supported:
+//!
+//! - The cache Cargo keeps in `~/.cargo/registry/index/<registry>/.cache`,
+//!   for both `git` and sparse indices
+//! - A mirror of a sparse index, such as one downloaded from
+//!   `https://index.crates.io`
+//! - A checkout of a `git` index, such as
+//!   `https://github.com/rust-lang/crates.io-index`
+//!
+//! See [the Cargo book](https://doc.rust-lang.org/cargo/reference/registry-index.html)
+//! for the format of an index.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::NameVersion;
+
+/// The version of the format of files in the Cargo index cache that can be
+/// read
+const CACHE_VERSION: u8 = 3;
+
+/// A version of a crate as listed in a registry index, ignoring fields that
+/// are not used
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexVersion {
+    pub name: String,
+    pub vers: String,
+    pub yanked: bool,
+}
+
+/// How the files of an index are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndexLayout {
+    /// Plain JSON lines, as in a sparse index mirror or a `git` checkout
+    Files,
+
+    /// The binary format of the Cargo index cache
+    Cache,
+}
+
+/// Reads crate versions from a local registry index, caching parsed files
+#[derive(Debug, Clone)]
+pub struct RegistryIndexClient {
+    root: PathBuf,
+    layout: IndexLayout,
+
+    /// Cache between crate name and its versions, `None` if the crate is not
+    /// in the index
+    cache: HashMap<String, Option<Vec<IndexVersion>>>,
+}
+
+impl RegistryIndexClient {
+    /// Creates a client for an index stored at `path`
+    ///
+    /// The path can either be a sparse index mirror or `git` index checkout,
+    /// or a registry directory in `~/.cargo/registry/index`, in which case its
+    /// `.cache` directory is used.
+    #[must_use]
+    pub fn new(path: &Path) -> Self {
+        let cache_dir = path.join(".cache");
+        let (root, layout) = if cache_dir.is_dir() {
+            (cache_dir, IndexLayout::Cache)
+        } else if path.file_name().is_some_and(|n| n == ".cache") {
+            (path.to_path_buf(), IndexLayout::Cache)
+        } else {
+            (path.to_path_buf(), IndexLayout::Files)
+        };
+
+        Self {
+            root,
+            layout,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Creates a client for the crates.io index cached by Cargo in
+    /// `<cargo_home>/registry/index`, preferring the sparse index
+    ///
+    /// Returns `None` if Cargo has not cached the crates.io index.
+    #[must_use]
+    pub fn from_cargo_home(cargo_home: &Path) -> Option<Self> {
+        let index_dir = cargo_home.join("registry").join("index");
+        ["index.crates.io-", "github.com-"]
+            .iter()
+            .find_map(|prefix| {
+                fs::read_dir(&index_dir).ok()?.flatten().find_map(|e| {
+                    let is_match = e
+                        .file_name()
+                        .to_str()
+                        .is_some_and(|n| n.starts_with(prefix));
+                    (is_match && e.path().join(".cache").is_dir())
+                        .then(|| e.path())
+                })
+            })
+            .map(|p| Self::new(&p))
+    }
+
+    /// Retrieves all versions of a crate listed in the index, in the order
+    /// they were published
+    ///
+    /// Returns `None` if the crate is not in the index, and caches it as such.
+    pub fn versions(&mut self, crate_name: &str) -> Option<&Vec<IndexVersion>> {
+        let path = self.root.join(index_path(crate_name));
+        let layout = self.layout;
+        self.cache
+            .entry(crate_name.to_string())
+            .or_insert_with(|| {
+                let raw = fs::read(&path).ok()?;
+                let versions = match layout {
+                    IndexLayout::Files => parse_index_file(&raw),
+                    IndexLayout::Cache => parse_cache_file(&raw),
+                };
+                if versions.is_none() {
+                    eprintln!(
+                        "could not parse registry index file {}",
+                        path.to_string_lossy()
+                    );
+                }
+                versions
+            })
+            .as_ref()
+    }
+
+    /// Returns if this version is yanked, `None` if the version is not in the
+    /// index
+    pub fn yanked(&mut self, name_version: &NameVersion) -> Option<bool> {
+        self.versions(&name_version.name).and_then(|versions| {
+            versions.iter().find_map(|v| {
+                rustsec::Version::parse(&v.vers)
+                    .is_ok_and(|parsed| parsed == name_version.version)
+                    .then_some(v.yanked)
+            })
+        })
+    }
+
+    /// Retrieves all versions of a crate that have been yanked
+    pub fn yanked_versions(&mut self, crate_name: &str) -> Option<Vec<String>> {
+        self.versions(crate_name).map(|versions| {
+            versions
+                .iter()
+                .filter(|v| v.yanked)
+                .map(|v| v.vers.clone())
+                .collect()
+        })
+    }
+}
+
+/// The path of the file for a crate in an index, relative to the index root
+///
+/// Names are lowercased, and split into directories on the first characters of
+/// the name, like `se/rd/serde` and `3/s/syn`.
+fn index_path(crate_name: &str) -> PathBuf {
+    let name = crate_name.to_lowercase();
+    match name.len() {
+        1 => ["1", &name].iter().collect(),
+        2 => ["2", &name].iter().collect(),
+        3 => ["3", &name[..1], &name].iter().collect(),
+        _ => [&name[..2], &name[2..4], &name].iter().collect(),
+    }
+}
+
+/// Parses an index file with one JSON object per line
+fn parse_index_file(raw: &[u8]) -> Option<Vec<IndexVersion>> {
+    std::str::from_utf8(raw)
+        .ok()?
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+/// Parses a file in the Cargo index cache
+///
+/// These start with the cache version as a byte and the index format version
+/// as a little endian `u32`, followed by the index revision it was cached
+/// from. Then each version of the crate follows as the version number and its
+/// JSON line, with all strings separated by null bytes.
+fn parse_cache_file(raw: &[u8]) -> Option<Vec<IndexVersion>> {
+    let (&cache_version, rest) = raw.split_first()?;
+    if cache_version != CACHE_VERSION {
+        return None;
+    }
+
+    let mut fields = rest.get(4..)?.split(|b| *b == 0);
+
+    // The index revision is not used
+    fields.next()?;
+
+    let mut versions = Vec::new();
+    while let (Some(_), Some(json)) = (fields.next(), fields.next()) {
+        versions.push(serde_json::from_slice(json).ok()?);
+    }
+    Some(versions)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use test_case::test_case;
+
+    use super::{
+        index_path, parse_cache_file, parse_index_file, RegistryIndexClient,
+    };
+
+    #[test_case("a" => PathBuf::from("1/a") ; "one character")]
+    #[test_case("cc" => PathBuf::from("2/cc") ; "two characters")]
+    #[test_case("syn" => PathBuf::from("3/s/syn") ; "three characters")]
+    #[test_case("Serde" => PathBuf::from("se/rd/serde") ; "uppercase")]
+    fn path_in_index(name: &str) -> PathBuf {
+        index_path(name)
+    }
+
+    #[test]
+    fn parse_cache() {
+        let mut raw = vec![3, 2, 0, 0, 0];
+        raw.extend_from_slice(b"etag: \"abc\"\0");
+        raw.extend_from_slice(
+            b"1.0.0\0{\"name\":\"a\",\"vers\":\"1.0.0\",\"yanked\":false}\0",
+        );
+        raw.extend_from_slice(
+            b"1.0.1\0{\"name\":\"a\",\"vers\":\"1.0.1\",\"yanked\":true}\0",
+        );
+
+        let versions = parse_cache_file(&raw).unwrap();
+        assert_eq!(
+            versions
+                .iter()
+                .map(|v| (v.vers.as_str(), v.yanked))
+                .collect::<Vec<_>>(),
+            vec![("1.0.0", false), ("1.0.1", true)]
+        );
+    }
+
+    #[test_case(&[1, 0] ; "old cache version")]
+    #[test_case(&[] ; "empty")]
+    fn parse_unsupported_cache(raw: &[u8]) {
+        assert!(parse_cache_file(raw).is_none());
+    }
+
+    #[test]
+    fn parse_index() {
+        let raw = b"{\"name\":\"a\",\"vers\":\"1.0.0\",\"deps\":[],\"yanked\":false}\n{\"name\":\"a\",\"vers\":\"1.1.0\",\"deps\":[],\"yanked\":true}\n";
+        let versions = parse_index_file(raw).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(versions[1].yanked);
+    }
+
+    #[test_case("test_data/fake_cargo_home" => true ; "cached crates.io index")]
+    #[test_case("test_data/fake_crates" => false ; "no registry index")]
+    fn index_in_cargo_home(cargo_home: &str) -> bool {
+        RegistryIndexClient::from_cargo_home(Path::new(cargo_home)).is_some()
+    }
+}
@@ -14,7 +14,7 @@ impl<'a> From<&'a str> for RepoId<'a> {
     fn from(url: &'a str) -> Self {
         match Url::parse(url) {
             Ok(u) => match u.host_str() {
-                Some(host) if host == "github.com" => {
+                Some("github.com") => {
                     // The two first parts of the path are owner and repo
                     if let Some(path) = u.path_segments() {
                         let owner_repo = path
@@ -40,7 +40,7 @@ impl<'a> From<&'a str> for RepoId<'a> {
                         RepoId::Unknown(url)
                     }
                 }
-                Some(host) if host == "gitlab.com" => RepoId::GitLab(url),
+                Some("gitlab.com") => RepoId::GitLab(url),
                 Some(_) => RepoId::Unknown(url),
                 None => {
                     eprintln!("found no host for repo url {url}");
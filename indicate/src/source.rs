@@ -0,0 +1,208 @@
+//! Types describing where a package comes from, such as a registry, a git
+//! repository or a local path
+//!
+//! See [the Cargo book](https://doc.rust-lang.org/cargo/reference/specifying-dependencies.html)
+//! for the different ways a dependency can be specified.
+
+use cargo_metadata::Package;
+use url::Url;
+
+/// The index URL of the crates.io registry, as used by `git` based indices
+pub const CRATES_IO_INDEX: &str =
+    "https://github.com/rust-lang/crates.io-index";
+
+/// The index URL of the crates.io registry, as used by the sparse protocol
+pub const CRATES_IO_SPARSE_INDEX: &str = "https://index.crates.io/";
+
+/// The kind of source a package is retrieved from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    /// A remote registry, such as crates.io
+    Registry,
+    Git,
+
+    /// A local path, such as the root package or workspace members
+    Path,
+
+    /// A registry or directory stored on the local file system, used for
+    /// vendored dependencies
+    LocalRegistry,
+}
+
+impl SourceKind {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceKind::Registry => "registry",
+            SourceKind::Git => "git",
+            SourceKind::Path => "path",
+            SourceKind::LocalRegistry => "local-registry",
+        }
+    }
+}
+
+/// Where a package comes from, parsed from the source identifier provided by
+/// `cargo metadata`, like
+/// `git+https://github.com/rust-lang/cfg-if?tag=1.0.0#<commit>`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Source {
+    kind: SourceKind,
+
+    /// The URL of the registry or repository, `None` for path sources
+    url: Option<String>,
+    rev: Option<String>,
+    branch: Option<String>,
+    tag: Option<String>,
+
+    /// The commit a git dependency was locked to
+    locked_commit: Option<String>,
+}
+
+impl Source {
+    /// Parses a source identifier as provided by `cargo metadata`, such as
+    /// `registry+https://github.com/rust-lang/crates.io-index`
+    ///
+    /// Unknown source kinds are assumed to be local paths.
+    #[must_use]
+    pub fn parse(repr: &str) -> Self {
+        let Some((kind, url)) = repr.split_once('+') else {
+            return Self::path();
+        };
+
+        let kind = match kind {
+            "registry" | "sparse" => SourceKind::Registry,
+            "git" => SourceKind::Git,
+            "local-registry" | "directory" => SourceKind::LocalRegistry,
+            _ => return Self::path(),
+        };
+
+        if kind != SourceKind::Git {
+            return Self {
+                kind,
+                url: Some(url.to_string()),
+                ..Self::path()
+            };
+        }
+
+        let Ok(mut parsed) = Url::parse(url) else {
+            return Self {
+                kind,
+                url: Some(url.to_string()),
+                ..Self::path()
+            };
+        };
+
+        let mut source = Self {
+            kind,
+            locked_commit: parsed.fragment().map(String::from),
+            ..Self::path()
+        };
+        for (key, value) in parsed.query_pairs() {
+            match key.as_ref() {
+                "rev" => source.rev = Some(value.into_owned()),
+                "branch" => source.branch = Some(value.into_owned()),
+                "tag" => source.tag = Some(value.into_owned()),
+                _ => (),
+            }
+        }
+
+        // The reference and locked commit are not part of the repository URL
+        parsed.set_query(None);
+        parsed.set_fragment(None);
+        source.url = Some(parsed.to_string());
+        source
+    }
+
+    fn path() -> Self {
+        Self {
+            kind: SourceKind::Path,
+            url: None,
+            rev: None,
+            branch: None,
+            tag: None,
+            locked_commit: None,
+        }
+    }
+
+    #[must_use]
+    pub fn kind(&self) -> SourceKind {
+        self.kind
+    }
+
+    #[must_use]
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    #[must_use]
+    pub fn rev(&self) -> Option<&str> {
+        self.rev.as_deref()
+    }
+
+    #[must_use]
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    #[must_use]
+    pub fn tag(&self) -> Option<&str> {
+        self.tag.as_deref()
+    }
+
+    #[must_use]
+    pub fn locked_commit(&self) -> Option<&str> {
+        self.locked_commit.as_deref()
+    }
+
+    /// Checks if this source is the crates.io registry, using either the `git`
+    /// or the sparse index
+    #[must_use]
+    pub fn is_crates_io(&self) -> bool {
+        self.kind == SourceKind::Registry
+            && self.url.as_deref().is_some_and(|u| {
+                u == CRATES_IO_INDEX
+                    || u.trim_end_matches('/')
+                        == CRATES_IO_SPARSE_INDEX.trim_end_matches('/')
+            })
+    }
+}
+
+impl From<&Package> for Source {
+    fn from(package: &Package) -> Self {
+        match &package.source {
+            Some(s) => Self::parse(&s.repr),
+            None => Self::path(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::{Source, SourceKind};
+
+    #[test_case("registry+https://github.com/rust-lang/crates.io-index" => (SourceKind::Registry, true) ; "crates io git index")]
+    #[test_case("sparse+https://index.crates.io/" => (SourceKind::Registry, true) ; "crates io sparse index")]
+    #[test_case("sparse+https://my-registry.example.com/index/" => (SourceKind::Registry, false) ; "alternative registry")]
+    #[test_case("git+https://github.com/rust-lang/cfg-if#abc" => (SourceKind::Git, false) ; "git repository")]
+    #[test_case("local-registry+file:///vendor" => (SourceKind::LocalRegistry, false) ; "local registry")]
+    #[test_case("directory+file:///vendor" => (SourceKind::LocalRegistry, false) ; "vendored directory")]
+    #[test_case("path+file:///some/package" => (SourceKind::Path, false) ; "path")]
+    fn parse_kind(repr: &str) -> (SourceKind, bool) {
+        let source = Source::parse(repr);
+        (source.kind(), source.is_crates_io())
+    }
+
+    #[test]
+    fn parse_git_reference() {
+        let source = Source::parse(
+            "git+https://github.com/rust-lang/cfg-if?branch=main&tag=1.0.0#abc",
+        );
+        assert_eq!(source.url(), Some("https://github.com/rust-lang/cfg-if"));
+        assert_eq!(source.branch(), Some("main"));
+        assert_eq!(source.tag(), Some("1.0.0"));
+        assert_eq!(source.rev(), None);
+        assert_eq!(source.locked_commit(), Some("abc"));
+    }
+}
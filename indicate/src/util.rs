@@ -1,14 +1,20 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     path::PathBuf,
     rc::Rc,
     sync::Arc,
 };
 
-use cargo_metadata::{DependencyKind, Metadata, Package};
+use cargo_metadata::{DependencyKind, Metadata, Package, PackageId, Target};
 use trustfall::{FieldValue, TransparentValue};
 
-use crate::adapter::{DirectDependencyMap, PackageMap};
+use crate::{
+    adapter::{
+        DependencyEdgeMap, DependentMap, DepthMap, DirectDependencyMap,
+        EnabledFeatureMap, PackageMap, PackageNameMap,
+    },
+    dependency::DependencyEdge,
+};
 
 /// Transform a result from [`execute_query`](trustfall::execute_query) to one where the fields can easily
 /// be serialized to JSON using [`TransparentValue`].
@@ -33,33 +39,65 @@ pub fn local_package_path(package: &Package) -> PathBuf {
     p
 }
 
-/// Parse metadata to create a map over direct dependencies for all packages
+/// Checks if a package is a procedural macro, i.e. its library is compiled
+/// and run by the compiler
+#[must_use]
+pub fn is_proc_macro(package: &Package) -> bool {
+    package
+        .targets
+        .iter()
+        .any(|t| t.kind.iter().any(|k| k == "proc-macro"))
+}
+
+/// Checks if a package has a build script (`build.rs`) that is run when
+/// compiling the package
+#[must_use]
+pub fn has_build_script(package: &Package) -> bool {
+    package.targets.iter().any(Target::is_custom_build)
+}
+
+/// Retrieves the minimum supported Rust version of a package, as written in
+/// its manifest
 ///
-/// Direct dependencies will only include 'normal' dependencies, i.e.
-/// not build nor test deps.
+/// `cargo_metadata` parses `rust-version` as a requirement, so the version is
+/// rebuilt from its single comparator.
+#[must_use]
+pub fn rust_version(package: &Package) -> Option<String> {
+    let c = package.rust_version.as_ref()?.comparators.first()?;
+    Some(match (c.minor, c.patch) {
+        (Some(minor), Some(patch)) => format!("{}.{minor}.{patch}", c.major),
+        (Some(minor), None) => format!("{}.{minor}", c.major),
+        _ => c.major.to_string(),
+    })
+}
+
+/// Parse metadata to create a map over direct dependencies of a specific
+/// kind for all packages
+///
+/// Passing [`DependencyKind::Normal`] will only include 'normal' dependencies,
+/// i.e. not build nor test deps.
 ///
 /// _Note_: This operation is quite expensive as it must traverse the dependency
 /// tree. Avoid if not required.
 #[must_use]
-pub fn get_direct_dependencies(metadata: &Metadata) -> DirectDependencyMap {
+pub fn get_direct_dependencies(
+    metadata: &Metadata,
+    kind: DependencyKind,
+) -> DirectDependencyMap {
     let mut direct_dependencies =
         HashMap::with_capacity(metadata.packages.len());
 
     for node in &metadata.resolve.as_ref().expect("No nodes found!").nodes {
         let id = node.id.clone();
 
-        // Filter out dependencies that are not normal
-        let normal_deps = node
+        // Filter out dependencies that are not of the requested kind
+        let kind_deps = node
             .deps
             .iter()
             .filter_map(|nd| {
-                if nd
-                    .dep_kinds
-                    .iter()
-                    .any(|dki| dki.kind == DependencyKind::Normal)
-                {
-                    // A dependency can have many kinds; We only care if it is
-                    // normal
+                if nd.dep_kinds.iter().any(|dki| dki.kind == kind) {
+                    // A dependency can have many kinds; We only care if one
+                    // of them is the requested one
                     Some(nd.pkg.clone())
                 } else {
                     None
@@ -67,12 +105,167 @@ pub fn get_direct_dependencies(metadata: &Metadata) -> DirectDependencyMap {
             })
             .collect::<Vec<_>>();
 
-        direct_dependencies.insert(id, Rc::new(normal_deps));
+        direct_dependencies.insert(id, Rc::new(kind_deps));
     }
 
     direct_dependencies
 }
 
+/// Inverts a map over direct dependencies, creating a map over the packages
+/// that directly depend on each package
+///
+/// All packages in the provided map will be present in the resulting map,
+/// even if nothing depends on them.
+#[must_use]
+pub fn get_dependents(
+    direct_dependencies: &DirectDependencyMap,
+) -> DependentMap {
+    let mut dependents: HashMap<PackageId, Vec<PackageId>> =
+        HashMap::with_capacity(direct_dependencies.len());
+
+    for (id, dependencies) in direct_dependencies {
+        dependents.entry(id.clone()).or_default();
+        for dependency_id in dependencies.iter() {
+            dependents
+                .entry(dependency_id.clone())
+                .or_default()
+                .push(id.clone());
+        }
+    }
+
+    dependents
+        .into_iter()
+        .map(|(id, mut d)| {
+            // The direct dependency map is unordered, but we want the same
+            // output every time
+            d.sort();
+            (id, Rc::new(d))
+        })
+        .collect()
+}
+
+/// Counts the number of packages that depend on a package, directly or
+/// indirectly
+#[must_use]
+pub fn transitive_dependents_count(
+    dependents: &DependentMap,
+    package_id: &PackageId,
+) -> usize {
+    let mut visited = HashSet::new();
+    let mut stack = vec![package_id];
+
+    while let Some(id) = stack.pop() {
+        if let Some(ds) = dependents.get(id) {
+            for d in ds.iter() {
+                if d != package_id && visited.insert(d) {
+                    stack.push(d);
+                }
+            }
+        }
+    }
+
+    visited.len()
+}
+
+/// Calculates the shortest distance from any of the root packages to all
+/// packages reachable through the direct dependencies
+///
+/// The root packages themselves have depth `0`.
+#[must_use]
+pub fn get_depths(
+    direct_dependencies: &DirectDependencyMap,
+    root_ids: &[PackageId],
+) -> DepthMap {
+    let mut depths: DepthMap =
+        root_ids.iter().map(|id| (id.clone(), 0)).collect();
+    let mut queue = root_ids.iter().collect::<VecDeque<_>>();
+
+    while let Some(id) = queue.pop_front() {
+        let depth = depths[id];
+        for d in direct_dependencies
+            .get(id)
+            .into_iter()
+            .flat_map(|ds| ds.iter())
+        {
+            if !depths.contains_key(d) {
+                depths.insert(d.clone(), depth + 1);
+                queue.push_back(d);
+            }
+        }
+    }
+
+    depths
+}
+
+/// Finds paths from any of the root packages to a package, ordered from the
+/// root package to the provided package
+///
+/// Paths are found depth-first by walking the dependents of the package,
+/// preferring dependents closer to a root package, so the first path is always
+/// a shortest one. Dependents not reachable from a root package are never
+/// walked, and the search stops once `max_paths` paths have been found. The
+/// returned paths are sorted by length.
+#[must_use]
+pub fn paths_from_roots(
+    dependents: &DependentMap,
+    depths: &DepthMap,
+    root_ids: &[PackageId],
+    package_id: &PackageId,
+    max_paths: usize,
+) -> Vec<Vec<PackageId>> {
+    let mut paths = Vec::new();
+    if !depths.contains_key(package_id) {
+        return paths;
+    }
+
+    // Partial paths are stored in reverse, from the package towards a root
+    let mut stack = vec![vec![package_id]];
+    while let Some(partial) = stack.pop() {
+        if paths.len() >= max_paths {
+            break;
+        }
+
+        // Never empty, we start with one element and only add to it
+        let last = *partial.last().unwrap();
+        if root_ids.contains(last) {
+            paths.push(partial.iter().rev().map(|&id| id.clone()).collect());
+            continue;
+        }
+
+        let mut next_ids = dependents
+            .get(last)
+            .into_iter()
+            .flat_map(|ds| ds.iter())
+            // Normal dependencies can not be cyclic, so all dependents with a
+            // depth lead to a root package
+            .filter(|d| depths.contains_key(*d) && !partial.contains(d))
+            .collect::<Vec<_>>();
+
+        // The stack is popped from the end, so the closest should be last
+        next_ids.sort_by_key(|d| std::cmp::Reverse(depths[*d]));
+        for d in next_ids {
+            let mut next = partial.clone();
+            next.push(d);
+            stack.push(next);
+        }
+    }
+
+    paths.sort_by_key(Vec::len);
+    paths
+}
+
+/// Parses the name of a dependency kind as used in `Cargo.toml` and the
+/// schema, i.e. `normal`, `dev` or `build`
+#[must_use]
+pub fn parse_dependency_kind(kind: &str) -> Option<DependencyKind> {
+    match kind {
+        "normal" => Some(DependencyKind::Normal),
+        "dev" => Some(DependencyKind::Development),
+        "build" => Some(DependencyKind::Build),
+        _ => None,
+    }
+}
+
 /// Parse metadata to create a map over packages
 #[must_use]
 pub fn get_packages(metadata: &Metadata) -> PackageMap {
@@ -86,3 +279,109 @@ pub fn get_packages(metadata: &Metadata) -> PackageMap {
 
     packages
 }
+
+/// Groups all packages by their crate name, with the packages of each name
+/// sorted by version
+#[must_use]
+pub fn get_packages_by_name(packages: &PackageMap) -> PackageNameMap {
+    let mut packages_by_name: BTreeMap<String, Vec<Rc<Package>>> =
+        BTreeMap::new();
+    for p in packages.values() {
+        packages_by_name
+            .entry(p.name.clone())
+            .or_default()
+            .push(Rc::clone(p));
+    }
+
+    packages_by_name
+        .into_iter()
+        .map(|(name, mut packages)| {
+            // Same name and version may come from different sources
+            packages.sort_by(|a, b| {
+                a.version.cmp(&b.version).then_with(|| a.id.cmp(&b.id))
+            });
+            (name, Rc::new(packages))
+        })
+        .collect()
+}
+
+/// Parse metadata to create a map over all dependency edges for all packages
+///
+/// Unlike [`get_direct_dependencies`], this includes all kinds of dependencies,
+/// and one edge is created for each kind and target a dependency is used for.
+/// Each edge is matched against the dependency as declared in the manifest of
+/// the dependent package, to retrieve information such as renames and
+/// features.
+#[must_use]
+pub fn get_dependency_edges(
+    metadata: &Metadata,
+    packages: &PackageMap,
+) -> DependencyEdgeMap {
+    let mut dependency_edges = HashMap::with_capacity(metadata.packages.len());
+
+    for node in &metadata.resolve.as_ref().expect("No nodes found!").nodes {
+        let dependent = packages.get(&node.id).unwrap_or_else(|| {
+            panic!("could not find package for resolved node {}", node.id)
+        });
+
+        let mut edges = Vec::with_capacity(node.deps.len());
+        for nd in &node.deps {
+            let package = packages.get(&nd.pkg).unwrap_or_else(|| {
+                panic!("could not find package for dependency {}", nd.pkg)
+            });
+
+            // The name used in code, which is the rename if the dependency
+            // is renamed, and otherwise the name of the library, which may
+            // differ from the package name (e.g. `md-5` has library `md5`)
+            let code_name = nd.name.replace('-', "_");
+            let is_renamed = dependent.dependencies.iter().any(|d| {
+                d.rename.as_deref().map(|r| r.replace('-', "_"))
+                    == Some(code_name.clone())
+            });
+            for dki in &nd.dep_kinds {
+                let candidates = dependent.dependencies.iter().filter(|d| {
+                    let same_name = match &d.rename {
+                        Some(rename) => rename.replace('-', "_") == code_name,
+                        None => !is_renamed && d.name == package.name,
+                    };
+                    same_name && d.kind == dki.kind && d.target == dki.target
+                });
+
+                // Prefer the declaration whose requirement matches the
+                // resolved version, but fall back to any with the same name
+                // since e.g. `*` does not match pre-releases
+                let declaration = candidates
+                    .clone()
+                    .find(|d| d.req.matches(&package.version))
+                    .or_else(|| candidates.clone().next())
+                    .cloned();
+
+                edges.push(Rc::new(DependencyEdge::new(
+                    dki.kind,
+                    dki.target.clone(),
+                    declaration,
+                    Rc::clone(package),
+                )));
+            }
+        }
+
+        dependency_edges.insert(node.id.clone(), Rc::new(edges));
+    }
+
+    dependency_edges
+}
+
+/// Parse metadata to create a map over what features are enabled for each
+/// package in the resolved dependency graph
+#[must_use]
+pub fn get_enabled_features(metadata: &Metadata) -> EnabledFeatureMap {
+    let nodes = &metadata.resolve.as_ref().expect("No nodes found!").nodes;
+    let mut enabled_features = HashMap::with_capacity(nodes.len());
+
+    for node in nodes {
+        enabled_features
+            .insert(node.id.clone(), Rc::new(node.features.clone()));
+    }
+
+    enabled_features
+}
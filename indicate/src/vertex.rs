@@ -3,15 +3,22 @@
 
 use std::{rc::Rc, sync::Arc};
 
-use cargo_metadata::Package;
+use cargo_metadata::{Package, Target};
+use crates_io_api::User;
 use octorust::types::{FullRepository, PublicUser};
 use rustsec::{advisory::affected::FunctionPath, Advisory, VersionReq};
 use trustfall::provider::TrustfallEnumVertex;
 
 use crate::{
     code_stats::{LanguageBlob, LanguageCodeStats},
+    crates_io::{DownloadPoint, PublisherHistoryEntry},
+    dependency::{DependencyEdge, DependencyPath},
+    duplicate::DuplicateGroup,
+    feature::Feature,
     geiger::{GeigerCategories, GeigerCount, GeigerUnsafety},
-    NameVersion,
+    license::{License, LicenseExpression, LicenseFile},
+    registry::RegistryPackage,
+    source::Source,
 };
 
 /// A node in the GraphQL schema as defined in the schema.
@@ -21,7 +28,19 @@ use crate::{
 #[derive(Debug, Clone, TrustfallEnumVertex)]
 pub enum Vertex {
     Package(Rc<Package>),
-    CratesIoStats(NameVersion),
+    DependencyEdge(Rc<DependencyEdge>),
+    Feature(Rc<Feature>),
+    Target(Rc<Target>),
+    Source(Rc<Source>),
+    DuplicateGroup(Rc<DuplicateGroup>),
+    DependencyPath(Rc<DependencyPath>),
+    LicenseExpression(Rc<LicenseExpression>),
+    License(License),
+    LicenseFile(Rc<LicenseFile>),
+    CratesIoStats(RegistryPackage),
+    CratesIoUser(Rc<User>),
+    PublisherHistory(Rc<PublisherHistoryEntry>),
+    DownloadPoint(DownloadPoint),
 
     #[trustfall(skip_conversion)]
     Webpage(String),